@@ -10,32 +10,58 @@ use std::{collections, env, fs, io::Write, path};
 
 use serde_derive::{Deserialize, Serialize};
 
-use ckb_chain_spec as spec;
+use ckb_chain_spec::{self as spec, consensus::Consensus};
 use ckb_resource as res;
-use ckb_types::{packed, prelude::*, H256};
+use ckb_types::{core::Capacity, packed, prelude::*, H256, U256};
+
+#[path = "src/derivation.rs"]
+mod derivation;
+#[path = "src/raw.rs"]
+mod raw;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-struct SystemCell {
-    path: String,
-    tx_hash: H256,
-    index: usize,
-    data_hash: H256,
-    type_hash: Option<H256>,
+struct ConsensusParams {
+    id: String,
+    genesis_hash: H256,
+    genesis_epoch_length: u64,
+    epoch_duration_target: u64,
+    max_block_cycles: u64,
+    max_block_bytes: u64,
+    max_uncles_num: usize,
+    orphan_rate_target_numer: u64,
+    orphan_rate_target_denom: u64,
+    proposer_reward_ratio_numer: u64,
+    proposer_reward_ratio_denom: u64,
+    max_block_proposals_limit: u64,
+    cellbase_maturity: u64,
+    median_time_block_count: usize,
+    tx_proposal_window_closest: u64,
+    tx_proposal_window_farthest: u64,
+    primary_epoch_reward_halving_interval: u64,
+    initial_primary_epoch_reward: u64,
+    secondary_epoch_reward: u64,
+    block_version: u32,
+    tx_version: u32,
+    type_id_code_hash: H256,
+    permanent_difficulty_in_dummy: bool,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-struct DepGroupCell {
-    included_cells: Vec<String>,
-    tx_hash: H256,
-    index: usize,
+struct IssuedCell {
+    index: u32,
+    capacity: u64,
+    code_hash: H256,
+    hash_type: u8,
+    args: Vec<u8>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-struct SpecHashes {
-    genesis: H256,
-    cellbase: H256,
-    system_cells: Vec<SystemCell>,
-    dep_groups: Vec<DepGroupCell>,
+struct Issuance {
+    cellbase_hash: H256,
+    total: u64,
+    system_cells_occupied: u64,
+    burned: u64,
+    issued_cells: Vec<IssuedCell>,
 }
 
 fn create_template_context(spec: &str) -> res::TemplateContext {
@@ -52,76 +78,121 @@ fn create_template_context(spec: &str) -> res::TemplateContext {
     )
 }
 
-fn load_hashes_from_chain_spec(mut chain_spec: spec::ChainSpec) -> SpecHashes {
-    let spec_name = &chain_spec.name;
-    let hash_option = chain_spec.genesis.hash.take();
-    let consensus = chain_spec
-        .build_consensus()
-        .unwrap_or_else(|_| panic!("failed to build consensus for {}", spec_name));
-    if let Some(hash) = hash_option {
-        let genesis_hash: H256 = consensus.genesis_hash().unpack();
-        if hash != genesis_hash {
-            panic!(
-                "Genesis hash unmatched in {} chainspec config file: in file {:#x}, actual {:#x}",
-                spec_name, hash, genesis_hash
-            );
-        }
+fn u256_to_u64(value: &U256) -> u64 {
+    let lower = value.low_u64();
+    assert!(&U256::from(lower) == value, "{} is overflow", value);
+    lower
+}
+
+fn load_consensus_params_from_consensus(consensus: &Consensus) -> ConsensusParams {
+    ConsensusParams {
+        id: consensus.id.clone(),
+        genesis_hash: consensus.genesis_hash.unpack(),
+        genesis_epoch_length: consensus.genesis_epoch_ext.length(),
+        epoch_duration_target: consensus.epoch_duration_target,
+        max_block_cycles: consensus.max_block_cycles,
+        max_block_bytes: consensus.max_block_bytes,
+        max_uncles_num: consensus.max_uncles_num,
+        orphan_rate_target_numer: u256_to_u64(consensus.orphan_rate_target.numer()),
+        orphan_rate_target_denom: u256_to_u64(consensus.orphan_rate_target.denom()),
+        proposer_reward_ratio_numer: consensus.proposer_reward_ratio.numer(),
+        proposer_reward_ratio_denom: consensus.proposer_reward_ratio.denom(),
+        max_block_proposals_limit: consensus.max_block_proposals_limit,
+        cellbase_maturity: consensus.cellbase_maturity.full_value(),
+        median_time_block_count: consensus.median_time_block_count,
+        tx_proposal_window_closest: consensus.tx_proposal_window.closest(),
+        tx_proposal_window_farthest: consensus.tx_proposal_window.farthest(),
+        primary_epoch_reward_halving_interval: consensus.primary_epoch_reward_halving_interval,
+        initial_primary_epoch_reward: consensus.initial_primary_epoch_reward.as_u64(),
+        secondary_epoch_reward: consensus.secondary_epoch_reward.as_u64(),
+        block_version: consensus.block_version,
+        tx_version: consensus.tx_version,
+        type_id_code_hash: consensus.type_id_code_hash.clone(),
+        permanent_difficulty_in_dummy: consensus.permanent_difficulty_in_dummy,
     }
+}
 
+fn load_issuance_from_consensus(chain_spec: &spec::ChainSpec, consensus: &Consensus) -> Issuance {
+    let spec_name = &chain_spec.name;
     let block = consensus.genesis_block();
     let cellbase = &block.transactions()[0];
-    let dep_group_tx = &block.transactions()[1];
 
-    let cells_hashes = chain_spec
+    let outputs = cellbase.outputs().into_iter().collect::<Vec<_>>();
+    let mut cursor = 0;
+    let issued_cells = chain_spec
         .genesis
-        .system_cells
+        .issued_cells
         .iter()
-        .map(|system_cell| &system_cell.file)
-        .zip(
-            cellbase
-                .outputs()
-                .into_iter()
-                .zip(cellbase.outputs_data().into_iter())
-                .skip(1),
-        )
-        .enumerate()
-        .map(|(index_minus_one, (resource, (output, data)))| {
-            let data_hash: H256 = packed::CellOutput::calc_data_hash(&data.raw_data()).unpack();
-            let type_hash: Option<H256> = output
-                .type_()
-                .to_opt()
-                .map(|script| script.calc_script_hash().unpack());
-            SystemCell {
-                path: resource.to_string(),
-                tx_hash: cellbase.hash().unpack(),
-                index: index_minus_one + 1,
-                data_hash,
-                type_hash,
+        .map(|issued_cell| {
+            let lock: packed::Script = issued_cell.lock.clone().into();
+            let capacity = issued_cell.capacity.pack();
+            let index = outputs
+                .iter()
+                .skip(cursor)
+                .position(|output| output.lock() == lock && output.capacity() == capacity)
+                .map(|offset| cursor + offset)
+                .unwrap_or_else(|| panic!("issued cell was not found in {}", spec_name));
+            cursor = index + 1;
+            IssuedCell {
+                index: index as u32,
+                capacity: issued_cell.capacity.as_u64(),
+                code_hash: lock.code_hash().unpack(),
+                hash_type: lock.hash_type().into(),
+                args: lock.args().raw_data().to_vec(),
             }
         })
-        .collect();
+        .collect::<Vec<_>>();
 
-    let dep_groups = chain_spec
-        .genesis
-        .dep_groups
+    let system_cells_occupied = cellbase
+        .outputs_with_data_iter()
+        .skip(1)
+        .take(chain_spec.genesis.system_cells.len())
+        .map(|(output, data)| {
+            output
+                .occupied_capacity(Capacity::bytes(data.len()).unwrap())
+                .unwrap()
+                .as_u64()
+        })
+        .sum();
+
+    let total = block
+        .transactions()
         .iter()
-        .enumerate()
-        .map(|(index, dep_group)| DepGroupCell {
-            included_cells: dep_group
-                .files
-                .iter()
-                .map(|res| res.to_string())
-                .collect::<Vec<_>>(),
-            tx_hash: dep_group_tx.hash().unpack(),
-            index,
+        .flat_map(|tx| tx.outputs().into_iter())
+        .map(|output| {
+            let capacity: u64 = output.capacity().unpack();
+            capacity
         })
-        .collect::<Vec<_>>();
+        .sum();
+
+    // The system cells and the dep groups are locked by the zero code hash too, but they are
+    // not counted, only the issued cells are.
+    let burned = chain_spec
+        .genesis
+        .issued_cells
+        .iter()
+        .filter(|issued_cell| issued_cell.lock.code_hash == H256::default())
+        .map(|issued_cell| issued_cell.capacity.as_u64())
+        .sum();
 
-    SpecHashes {
-        genesis: consensus.genesis_hash().unpack(),
-        cellbase: cellbase.hash().unpack(),
-        system_cells: cells_hashes,
-        dep_groups,
+    Issuance {
+        cellbase_hash: cellbase.hash().unpack(),
+        total,
+        system_cells_occupied,
+        burned,
+        issued_cells,
+    }
+}
+
+fn export_genesis_block(consensus: &Consensus, file: &path::Path) {
+    fs::write(file, consensus.genesis_block().data().as_slice())
+        .unwrap_or_else(|_| panic!("failed to write file {:?}", file));
+}
+
+fn write_separator(fd: &mut fs::File, file: &path::Path, first: bool) {
+    if !first {
+        fd.write_all(b"\n")
+            .unwrap_or_else(|_| panic!("failed to write file {:?}", file));
     }
 }
 
@@ -130,10 +201,17 @@ fn main() {
 
     let hashes_filename = "hashes.toml";
     let hashes_file = path::Path::new(&out_dir).join(hashes_filename);
+    let consensus_filename = "consensus.toml";
+    let consensus_file = path::Path::new(&out_dir).join(consensus_filename);
+    let issuance_filename = "issuance.toml";
+    let issuance_file = path::Path::new(&out_dir).join(issuance_filename);
 
-    let mut hashes_fd = fs::File::create(&hashes_file)
-        .unwrap_or_else(|_| panic!("failed to create file {:?}", hashes_file));
+    let mut consensus_fd = fs::File::create(&consensus_file)
+        .unwrap_or_else(|_| panic!("failed to create file {:?}", consensus_file));
+    let mut issuance_fd = fs::File::create(&issuance_file)
+        .unwrap_or_else(|_| panic!("failed to create file {:?}", issuance_file));
     let mut first = true;
+    let mut spec_hashes_map = collections::BTreeMap::default();
 
     for (name, spec_name) in &[
         ("mainnet", "mainnet"),
@@ -141,15 +219,12 @@ fn main() {
         ("staging", "staging"),
         ("develop", "dev"),
     ] {
-        if first {
-            first = false;
-        } else {
-            hashes_fd
-                .write_all(b"\n")
-                .unwrap_or_else(|_| panic!("failed to write file {:?}", hashes_file));
-        }
+        write_separator(&mut consensus_fd, &consensus_file, first);
+        write_separator(&mut issuance_fd, &issuance_file, first);
+        first = false;
         let dir = path::Path::new(&out_dir).join(name);
         fs::create_dir_all(&dir).unwrap_or_else(|_| panic!("failed to create directory {:?}", dir));
+        let genesis_file = dir.join("genesis.bin");
         res::Resource::bundled_ckb_config()
             .export(&create_template_context(spec_name), dir)
             .unwrap_or_else(|_| panic!("failed to export ckb config for {}", name));
@@ -157,12 +232,33 @@ fn main() {
         let chain_spec = spec::ChainSpec::load_from(&bundled)
             .unwrap_or_else(|_| panic!("failed to load ckb chain spec for {}", name));
         let spec_name = chain_spec.name.clone();
-        let spec_hashes = load_hashes_from_chain_spec(chain_spec);
-        let mut spec_hashes_map = collections::BTreeMap::default();
-        spec_hashes_map.insert(spec_name, spec_hashes);
-        let spec_hashes_string = toml::to_string(&spec_hashes_map).unwrap();
-        hashes_fd
-            .write_all(spec_hashes_string.as_bytes())
-            .unwrap_or_else(|_| panic!("failed to write file {:?}", hashes_file));
+        let consensus =
+            derivation::build_consensus(&chain_spec).unwrap_or_else(|err| panic!("{}", err));
+        let consensus_params = load_consensus_params_from_consensus(&consensus);
+        let issuance = load_issuance_from_consensus(&chain_spec, &consensus);
+        export_genesis_block(&consensus, &genesis_file);
+        let spec_hashes = derivation::load_hashes_from_consensus(&chain_spec, &consensus)
+            .unwrap_or_else(|err| panic!("{}", err));
+        spec_hashes_map.insert(spec_name.clone(), spec_hashes);
+        let mut consensus_params_map = collections::BTreeMap::default();
+        consensus_params_map.insert(spec_name.clone(), consensus_params);
+        let consensus_params_string = toml::to_string(&consensus_params_map).unwrap();
+        consensus_fd
+            .write_all(consensus_params_string.as_bytes())
+            .unwrap_or_else(|_| panic!("failed to write file {:?}", consensus_file));
+        let mut issuance_map = collections::BTreeMap::default();
+        issuance_map.insert(spec_name, issuance);
+        let issuance_string = toml::to_string(&issuance_map).unwrap();
+        issuance_fd
+            .write_all(issuance_string.as_bytes())
+            .unwrap_or_else(|_| panic!("failed to write file {:?}", issuance_file));
     }
+
+    let system_deps = raw::RawSystemDeps {
+        version: raw::FORMAT_VERSION,
+        specs: spec_hashes_map,
+    };
+    let system_deps_string = toml::to_string(&system_deps).unwrap();
+    fs::write(&hashes_file, system_deps_string)
+        .unwrap_or_else(|_| panic!("failed to write file {:?}", hashes_file));
 }
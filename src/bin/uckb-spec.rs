@@ -0,0 +1,338 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::{error, process, str::FromStr};
+
+use clap::{crate_version, App, Arg};
+use serde_derive::Serialize;
+
+use uckb_spec::{blockchain::Network, BaseSerializedSize, BootNodes, ConsensusParams, SystemDeps};
+
+type Result<T> = ::std::result::Result<T, Box<dyn error::Error>>;
+
+const SECTIONS: &[&str] = &[
+    "all",
+    "genesis",
+    "system-cells",
+    "dep-groups",
+    "bootnodes",
+    "consensus",
+    "serialized-size",
+];
+
+#[derive(Serialize)]
+struct Report {
+    network: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    genesis: Option<GenesisReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system_cells: Option<Vec<SystemCellReport>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dep_groups: Option<Vec<DepGroupReport>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bootnodes: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    consensus: Option<ConsensusReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    serialized_size: Option<SerializedSizeReport>,
+}
+
+#[derive(Serialize)]
+struct GenesisReport {
+    genesis_hash: String,
+    cellbase_hash: String,
+}
+
+#[derive(Serialize)]
+struct SystemCellReport {
+    name: String,
+    tx_hash: String,
+    index: usize,
+    data_hash: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    type_hash: Option<String>,
+}
+
+#[derive(Serialize)]
+struct DepGroupReport {
+    name: String,
+    tx_hash: String,
+    index: usize,
+}
+
+#[derive(Serialize)]
+struct ConsensusReport {
+    id: String,
+    genesis_epoch_length: u64,
+    epoch_duration_target: u64,
+    max_block_cycles: u64,
+    max_block_bytes: u64,
+    max_uncles_num: usize,
+    max_block_proposals_limit: u64,
+    cellbase_maturity: String,
+    median_time_block_count: usize,
+    tx_proposal_window_closest: u64,
+    tx_proposal_window_farthest: u64,
+    primary_epoch_reward_halving_interval: u64,
+    initial_primary_epoch_reward: u64,
+    secondary_epoch_reward: u64,
+    block_version: u32,
+    tx_version: u32,
+    permanent_difficulty_in_dummy: bool,
+}
+
+#[derive(Serialize)]
+struct SerializedSizeReport {
+    block: usize,
+    transaction: usize,
+    cell_input: usize,
+    cell_output: usize,
+    witness: usize,
+}
+
+fn build_report(network: Network, section: &str) -> Result<Report> {
+    let includes = |name: &str| section == "all" || section == name;
+    let mut report = Report {
+        network: network.to_string(),
+        genesis: None,
+        system_cells: None,
+        dep_groups: None,
+        bootnodes: None,
+        consensus: None,
+        serialized_size: None,
+    };
+    let system_deps = SystemDeps::read();
+    if includes("genesis") {
+        let spec = system_deps.lookup_spec(network)?;
+        report.genesis = Some(GenesisReport {
+            genesis_hash: format!("{:#x}", spec.genesis()),
+            cellbase_hash: format!("{:#x}", spec.cellbase()),
+        });
+    }
+    if includes("system-cells") {
+        let system_cells = system_deps
+            .lookup_spec(network)?
+            .iter_system_cells()
+            .map(|(bundled, cell)| SystemCellReport {
+                name: format!("{:?}", bundled),
+                tx_hash: format!("{:#x}", cell.tx_hash()),
+                index: cell.index(),
+                data_hash: format!("{:#x}", cell.data_hash()),
+                type_hash: cell.type_hash().as_ref().map(|h| format!("{:#x}", h)),
+            })
+            .collect();
+        report.system_cells = Some(system_cells);
+    }
+    if includes("dep-groups") {
+        let dep_groups = system_deps
+            .lookup_spec(network)?
+            .iter_dep_groups()
+            .map(|(dep_group_id, dep_group)| DepGroupReport {
+                name: format!("{:?}", dep_group_id),
+                tx_hash: format!("{:#x}", dep_group.tx_hash()),
+                index: dep_group.index(),
+            })
+            .collect();
+        report.dep_groups = Some(dep_groups);
+    }
+    if includes("bootnodes") {
+        let bootnodes = BootNodes::read()
+            .lookup(network)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        report.bootnodes = Some(bootnodes);
+    }
+    if includes("consensus") {
+        let consensus = ConsensusParams::read().lookup(network);
+        report.consensus = Some(ConsensusReport {
+            id: consensus.id().to_string(),
+            genesis_epoch_length: consensus.genesis_epoch_length(),
+            epoch_duration_target: consensus.epoch_duration_target(),
+            max_block_cycles: consensus.max_block_cycles(),
+            max_block_bytes: consensus.max_block_bytes(),
+            max_uncles_num: consensus.max_uncles_num(),
+            max_block_proposals_limit: consensus.max_block_proposals_limit(),
+            cellbase_maturity: consensus.cellbase_maturity().to_string(),
+            median_time_block_count: consensus.median_time_block_count(),
+            tx_proposal_window_closest: consensus.tx_proposal_window_closest(),
+            tx_proposal_window_farthest: consensus.tx_proposal_window_farthest(),
+            primary_epoch_reward_halving_interval: consensus
+                .primary_epoch_reward_halving_interval(),
+            initial_primary_epoch_reward: consensus.initial_primary_epoch_reward().as_u64(),
+            secondary_epoch_reward: consensus.secondary_epoch_reward().as_u64(),
+            block_version: consensus.block_version(),
+            tx_version: consensus.tx_version(),
+            permanent_difficulty_in_dummy: consensus.permanent_difficulty_in_dummy(),
+        });
+    }
+    if includes("serialized-size") {
+        let size = BaseSerializedSize::read();
+        report.serialized_size = Some(SerializedSizeReport {
+            block: size.block(),
+            transaction: size.transaction(),
+            cell_input: size.cell_input(),
+            cell_output: size.cell_output(),
+            witness: size.witness(),
+        });
+    }
+    Ok(report)
+}
+
+fn print_human(report: &Report) {
+    println!("Network: {}", report.network);
+    if let Some(ref genesis) = report.genesis {
+        println!();
+        println!("[Genesis]");
+        println!("    genesis hash:  {}", genesis.genesis_hash);
+        println!("    cellbase hash: {}", genesis.cellbase_hash);
+    }
+    if let Some(ref system_cells) = report.system_cells {
+        println!();
+        println!("[System Cells]");
+        for cell in system_cells {
+            println!("    {}:", cell.name);
+            println!("        out point: {}#{}", cell.tx_hash, cell.index);
+            println!("        data hash: {}", cell.data_hash);
+            if let Some(ref type_hash) = cell.type_hash {
+                println!("        type hash: {}", type_hash);
+            }
+        }
+    }
+    if let Some(ref dep_groups) = report.dep_groups {
+        println!();
+        println!("[Dep Groups]");
+        for dep_group in dep_groups {
+            println!("    {}:", dep_group.name);
+            println!(
+                "        out point: {}#{}",
+                dep_group.tx_hash, dep_group.index
+            );
+        }
+    }
+    if let Some(ref bootnodes) = report.bootnodes {
+        println!();
+        println!("[Bootnodes]");
+        for bootnode in bootnodes {
+            println!("    {}", bootnode);
+        }
+    }
+    if let Some(ref consensus) = report.consensus {
+        let window = format!(
+            "({}, {})",
+            consensus.tx_proposal_window_closest, consensus.tx_proposal_window_farthest
+        );
+        let items = vec![
+            ("id", consensus.id.clone()),
+            (
+                "genesis epoch length",
+                consensus.genesis_epoch_length.to_string(),
+            ),
+            (
+                "epoch duration target",
+                consensus.epoch_duration_target.to_string(),
+            ),
+            ("max block cycles", consensus.max_block_cycles.to_string()),
+            ("max block bytes", consensus.max_block_bytes.to_string()),
+            ("max uncles num", consensus.max_uncles_num.to_string()),
+            (
+                "max block proposals limit",
+                consensus.max_block_proposals_limit.to_string(),
+            ),
+            ("cellbase maturity", consensus.cellbase_maturity.clone()),
+            (
+                "median time block count",
+                consensus.median_time_block_count.to_string(),
+            ),
+            ("tx proposal window", window),
+            (
+                "reward halving interval",
+                consensus.primary_epoch_reward_halving_interval.to_string(),
+            ),
+            (
+                "initial primary reward",
+                consensus.initial_primary_epoch_reward.to_string(),
+            ),
+            (
+                "secondary epoch reward",
+                consensus.secondary_epoch_reward.to_string(),
+            ),
+            ("block version", consensus.block_version.to_string()),
+            ("tx version", consensus.tx_version.to_string()),
+            (
+                "permanent difficulty",
+                consensus.permanent_difficulty_in_dummy.to_string(),
+            ),
+        ];
+        println!();
+        println!("[Consensus]");
+        for (label, value) in items {
+            println!("    {:<28}{}", format!("{}:", label), value);
+        }
+    }
+    if let Some(ref size) = report.serialized_size {
+        println!();
+        println!("[Base Serialized Size]");
+        println!("    block:       {}", size.block);
+        println!("    transaction: {}", size.transaction);
+        println!("    cell input:  {}", size.cell_input);
+        println!("    cell output: {}", size.cell_output);
+        println!("    witness:     {}", size.witness);
+    }
+}
+
+fn execute() -> Result<()> {
+    let matches = App::new("uckb-spec")
+        .version(crate_version!())
+        .about("Inspect the [unofficial] CKB chain specification.")
+        .arg(
+            Arg::with_name("network")
+                .long("network")
+                .short("n")
+                .takes_value(true)
+                .possible_values(&["mainnet", "testnet", "staging", "develop"])
+                .default_value("mainnet")
+                .help("The network to inspect"),
+        )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .short("f")
+                .takes_value(true)
+                .possible_values(&["human", "json", "toml"])
+                .default_value("human")
+                .help("The output format"),
+        )
+        .arg(
+            Arg::with_name("section")
+                .possible_values(SECTIONS)
+                .default_value("all")
+                .help("The section of the specification to print"),
+        )
+        .get_matches();
+    let network = Network::from_str(matches.value_of("network").unwrap())?;
+    let section = matches.value_of("section").unwrap();
+    let report = build_report(network, section)?;
+    match matches.value_of("format").unwrap() {
+        "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+        "toml" => {
+            // Convert into a `toml::Value` first, so tables are always emitted after values.
+            let value = toml::Value::try_from(&report)?;
+            print!("{}", toml::to_string(&value)?);
+        }
+        _ => print_human(&report),
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(err) = execute() {
+        eprintln!("Error: {}", err);
+        process::exit(1);
+    }
+}
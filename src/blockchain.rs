@@ -10,7 +10,7 @@ use std::{convert::TryFrom, fmt, str::FromStr};
 
 use crate::error::{Error, Result};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Network {
     Mainnet,
     Testnet,
@@ -18,7 +18,7 @@ pub enum Network {
     Develop,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Bundled {
     Secp256k1Blake160,
     Secp256k1Blake160MultiSig,
@@ -26,7 +26,7 @@ pub enum Bundled {
     Dao,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum DepGroupId {
     Secp256k1Blake160,
     Secp256k1Blake160MultiSig,
@@ -34,6 +34,41 @@ pub enum DepGroupId {
 
 impl Network {
     pub(crate) const NAME: &'static str = "network";
+
+    /// All known networks.
+    pub fn all() -> &'static [Self] {
+        &[Self::Mainnet, Self::Testnet, Self::Staging, Self::Develop]
+    }
+
+    pub(crate) fn from_spec_name(spec_name: &str) -> Result<Self> {
+        match spec_name {
+            "ckb" => Ok("mainnet"),
+            "ckb_testnet" => Ok("testnet"),
+            "ckb_staging" => Ok("staging"),
+            "ckb_dev" => Ok("develop"),
+            s => Ok(s),
+        }
+        .and_then(Self::try_from)
+    }
+
+    /// The name in the chain specification, which is the inverse of `from_spec_name`.
+    pub(crate) fn spec_name(self) -> &'static str {
+        match self {
+            Self::Mainnet => "ckb",
+            Self::Testnet => "ckb_testnet",
+            Self::Staging => "ckb_staging",
+            Self::Develop => "ckb_dev",
+        }
+    }
+
+    pub(crate) fn bundled_spec_name(self) -> &'static str {
+        match self {
+            Self::Mainnet => "mainnet",
+            Self::Testnet => "testnet",
+            Self::Staging => "staging",
+            Self::Develop => "dev",
+        }
+    }
 }
 
 impl fmt::Display for Network {
@@ -70,6 +105,28 @@ impl TryFrom<&str> for Network {
 
 impl Bundled {
     pub(crate) const NAME: &'static str = "bundled";
+
+    /// All bundled system cells, in the same order as in the bundled chain specifications.
+    pub fn all() -> &'static [Self] {
+        &[
+            Self::Secp256k1Blake160,
+            Self::Dao,
+            Self::Secp256k1Data,
+            Self::Secp256k1Blake160MultiSig,
+        ]
+    }
+
+    /// The resource path of the system cell, which is the inverse of `from_str`.
+    pub(crate) fn resource_path(self) -> &'static str {
+        match self {
+            Self::Secp256k1Blake160 => "Bundled(specs/cells/secp256k1_blake160_sighash_all)",
+            Self::Secp256k1Blake160MultiSig => {
+                "Bundled(specs/cells/secp256k1_blake160_multisig_all)"
+            }
+            Self::Secp256k1Data => "Bundled(specs/cells/secp256k1_data)",
+            Self::Dao => "Bundled(specs/cells/dao)",
+        }
+    }
 }
 
 impl FromStr for Bundled {
@@ -96,6 +153,11 @@ impl TryFrom<&str> for Bundled {
 
 impl DepGroupId {
     pub(crate) const NAME: &'static str = "dep-group";
+
+    /// All bundled dep groups, in the same order as in the bundled chain specifications.
+    pub fn all() -> &'static [Self] {
+        &[Self::Secp256k1Blake160, Self::Secp256k1Blake160MultiSig]
+    }
 }
 
 impl TryFrom<&[Bundled]> for DepGroupId {
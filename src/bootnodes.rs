@@ -6,9 +6,20 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use std::{collections::HashMap, fs};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+    env, fmt, fs,
+    net::{Ipv4Addr, Ipv6Addr},
+    path::Path,
+    str::FromStr,
+};
 
-use tentacle::multiaddr::Multiaddr;
+use property::Property;
+use tentacle::{
+    multiaddr::{Multiaddr, Protocol},
+    secio::PeerId,
+};
 
 use crate::{
     blockchain::Network,
@@ -19,41 +30,89 @@ lazy_static::lazy_static! {
     static ref BOOTNODES: BootNodes = BootNodes::initialize().unwrap();
 }
 
-pub struct BootNodes(HashMap<Network, Vec<Multiaddr>>);
+#[derive(Clone, Default)]
+pub struct BootNodes {
+    addresses: HashMap<Network, Vec<Multiaddr>>,
+    nodes: HashMap<Network, Vec<BootNode>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Host {
+    Ip4(Ipv4Addr),
+    Ip6(Ipv6Addr),
+    Dns4(String),
+    Dns6(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Transport {
+    Tcp,
+    Ws,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BootNodeFilter {
+    Ipv4Only,
+    Ipv6Only,
+    DnsOnly,
+    TcpOnly,
+    WsOnly,
+}
+
+#[derive(Property, Debug, Clone)]
+#[property(get(public), set(disable), mut(disable))]
+pub struct BootNode {
+    address: Multiaddr,
+    host: Host,
+    port: u16,
+    transport: Transport,
+    peer_id: PeerId,
+}
 
 fn load_raw_bootnodes_from_file(chain: &str) -> Result<Vec<String>> {
     let out_dir = env!("OUT_DIR");
     let file_path = format!("{}/{}/ckb.toml", out_dir, chain);
-    fs::read_to_string(&file_path)?
+    let content = fs::read_to_string(&file_path)?;
+    load_raw_bootnodes_from_config(&content, chain).map_err(|err| match err {
+        Error::InvalidConfig(_, message) => {
+            Error::Unreachable(format!("{} for chain {}", message, chain))
+        }
+        err => err,
+    })
+}
+
+/// Reads `network.bootnodes` from the content of a `ckb.toml`, which may be supplied by users.
+fn load_raw_bootnodes_from_config(content: &str, source: &str) -> Result<Vec<String>> {
+    let invalid = |message: &'static str| Error::InvalidConfig(source.to_owned(), message);
+    content
         .parse::<toml::Value>()?
         .get("network")
-        .ok_or_else(|| Error::Unreachable(format!("`network` for chain {} was not found", chain)))?
+        .ok_or_else(|| invalid("`network` was not found"))?
         .get("bootnodes")
-        .ok_or_else(|| {
-            Error::Unreachable(format!(
-                "`network::bootnodes` for chain {} was not found",
-                chain
-            ))
-        })?
+        .ok_or_else(|| invalid("`network.bootnodes` was not found"))?
         .as_array()
-        .ok_or_else(|| {
-            Error::Unreachable(format!(
-                "`network::bootnodes` for chain {} was not array",
-                chain
-            ))
-        })?
+        .ok_or_else(|| invalid("`network.bootnodes` was not an array"))?
         .iter()
         .map(|v| {
-            v.as_str().map(ToOwned::to_owned).ok_or_else(|| {
-                Error::Unreachable(format!(
-                    "at least one item in `network::bootnodes` for chain {} was not string",
-                    chain
-                ))
-            })
+            v.as_str()
+                .map(ToOwned::to_owned)
+                .ok_or_else(|| invalid("an item in `network.bootnodes` was not a string"))
         })
         .collect::<Result<Vec<_>>>()
 }
 
+/// Parses a plain list of bootnodes, one address per line.
+///
+/// Empty lines and lines start with `#` are ignored.
+fn load_raw_bootnodes_from_list(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
 fn parse_multiaddrs(multiaddrs_str: &[String]) -> Result<Vec<Multiaddr>> {
     multiaddrs_str
         .iter()
@@ -64,13 +123,100 @@ fn parse_multiaddrs(multiaddrs_str: &[String]) -> Result<Vec<Multiaddr>> {
         .collect()
 }
 
+impl fmt::Display for Host {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Ip4(ip) => write!(f, "{}", ip),
+            Self::Ip6(ip) => write!(f, "{}", ip),
+            Self::Dns4(name) | Self::Dns6(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl TryFrom<Multiaddr> for BootNode {
+    type Error = Error;
+    fn try_from(address: Multiaddr) -> Result<Self> {
+        let invalid = |reason| Error::InvalidBootNode(address.to_string(), reason);
+        let mut host = None;
+        let mut port = None;
+        let mut transport = None;
+        let mut peer_id = None;
+        for protocol in address.iter() {
+            match protocol {
+                Protocol::Ip4(ip) if host.is_none() => host = Some(Host::Ip4(ip)),
+                Protocol::Ip6(ip) if host.is_none() => host = Some(Host::Ip6(ip)),
+                Protocol::Dns4(name) if host.is_none() => {
+                    host = Some(Host::Dns4(name.into_owned()))
+                }
+                Protocol::Dns6(name) if host.is_none() => {
+                    host = Some(Host::Dns6(name.into_owned()))
+                }
+                Protocol::Tcp(p) if port.is_none() => {
+                    port = Some(p);
+                    transport = Some(Transport::Tcp);
+                }
+                Protocol::Ws if transport == Some(Transport::Tcp) => {
+                    transport = Some(Transport::Ws);
+                }
+                Protocol::P2P(bytes) if peer_id.is_none() => {
+                    let id = PeerId::from_bytes(bytes.to_vec())
+                        .map_err(|_| invalid("the peer id is malformed"))?;
+                    peer_id = Some(id);
+                }
+                _ => return Err(invalid("unsupported protocol")),
+            }
+        }
+        Ok(Self {
+            host: host.ok_or_else(|| invalid("the host is missing"))?,
+            port: port.ok_or_else(|| invalid("the tcp port is missing"))?,
+            transport: transport.ok_or_else(|| invalid("the transport is missing"))?,
+            peer_id: peer_id.ok_or_else(|| invalid("the peer id is missing"))?,
+            address,
+        })
+    }
+}
+
+impl FromStr for BootNode {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let address = s
+            .parse::<Multiaddr>()
+            .map_err(|_| Error::Unknown("bootnode", s.to_owned()))?;
+        Self::try_from(address)
+    }
+}
+
+impl BootNode {
+    pub fn is_ipv4(&self) -> bool {
+        matches!(self.host, Host::Ip4(_))
+    }
+
+    pub fn is_ipv6(&self) -> bool {
+        matches!(self.host, Host::Ip6(_))
+    }
+
+    pub fn is_dns(&self) -> bool {
+        matches!(self.host, Host::Dns4(_) | Host::Dns6(_))
+    }
+
+    pub fn matches(&self, filter: BootNodeFilter) -> bool {
+        match filter {
+            BootNodeFilter::Ipv4Only => self.is_ipv4(),
+            BootNodeFilter::Ipv6Only => self.is_ipv6(),
+            BootNodeFilter::DnsOnly => self.is_dns(),
+            BootNodeFilter::TcpOnly => self.transport == Transport::Tcp,
+            BootNodeFilter::WsOnly => self.transport == Transport::Ws,
+        }
+    }
+}
+
 impl BootNodes {
     pub fn read() -> &'static Self {
         &BOOTNODES
     }
 
     fn initialize() -> Result<Self> {
-        let mut bootnodes = Self(HashMap::new());
+        let mut bootnodes = Self::default();
         bootnodes.initialize_network(Network::Mainnet)?;
         bootnodes.initialize_network(Network::Testnet)?;
         bootnodes.initialize_network(Network::Staging)?;
@@ -81,12 +227,193 @@ impl BootNodes {
     fn initialize_network(&mut self, network: Network) -> Result<()> {
         let chain = network.to_string().to_lowercase();
         let raw_bootnodes = load_raw_bootnodes_from_file(&chain)?;
-        self.0
-            .insert(network, parse_multiaddrs(&raw_bootnodes[..])?);
+        self.insert_raw(network, &raw_bootnodes[..])
+    }
+
+    fn insert_raw(&mut self, network: Network, raw_bootnodes: &[String]) -> Result<()> {
+        let nodes = parse_multiaddrs(raw_bootnodes)?
+            .into_iter()
+            .map(BootNode::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        self.insert(network, nodes);
         Ok(())
     }
 
+    fn insert(&mut self, network: Network, nodes: Vec<BootNode>) {
+        let mut peer_ids = HashSet::new();
+        let nodes = nodes
+            .into_iter()
+            .filter(|node| peer_ids.insert(node.peer_id.clone()))
+            .collect::<Vec<_>>();
+        let addresses = nodes.iter().map(|node| node.address.clone()).collect();
+        self.addresses.insert(network, addresses);
+        self.nodes.insert(network, nodes);
+    }
+
+    /// Returns a copy of the bundled bootnodes, for all networks.
+    pub fn bundled() -> Self {
+        Self::read().clone()
+    }
+
+    /// Loads bootnodes for a network from the `network.bootnodes` of a user-supplied `ckb.toml`.
+    pub fn from_config_file<P: AsRef<Path>>(network: Network, path: P) -> Result<Self> {
+        let content = fs::read_to_string(path.as_ref())?;
+        let chain = path.as_ref().display().to_string();
+        let raw_bootnodes = load_raw_bootnodes_from_config(&content, &chain)?;
+        let mut bootnodes = Self::default();
+        bootnodes.insert_raw(network, &raw_bootnodes[..])?;
+        Ok(bootnodes)
+    }
+
+    /// Loads bootnodes for a network from a plain list file, one address per line.
+    pub fn from_list_file<P: AsRef<Path>>(network: Network, path: P) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let raw_bootnodes = load_raw_bootnodes_from_list(&content);
+        let mut bootnodes = Self::default();
+        bootnodes.insert_raw(network, &raw_bootnodes[..])?;
+        Ok(bootnodes)
+    }
+
+    /// Loads bootnodes for a network from an environment variable, addresses are separated by
+    /// commas or whitespaces.
+    pub fn from_env(network: Network, key: &str) -> Result<Self> {
+        let value =
+            env::var(key).map_err(|_| Error::Unknown("environment variable", key.to_owned()))?;
+        let raw_bootnodes = value
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|addr| !addr.is_empty())
+            .map(ToOwned::to_owned)
+            .collect::<Vec<_>>();
+        let mut bootnodes = Self::default();
+        bootnodes.insert_raw(network, &raw_bootnodes[..])?;
+        Ok(bootnodes)
+    }
+
+    /// Merges two sets of bootnodes.
+    ///
+    /// Bootnodes in `self` take precedence: a bootnode in `other` is dropped if its peer id
+    /// already exists in `self` for the same network.
+    pub fn merge(mut self, other: &Self) -> Self {
+        for (network, other_nodes) in &other.nodes {
+            let mut nodes = self.nodes.remove(network).unwrap_or_default();
+            nodes.extend(other_nodes.iter().cloned());
+            self.insert(*network, nodes);
+        }
+        self
+    }
+
     pub fn lookup(&self, network: Network) -> &[Multiaddr] {
-        self.0.get(&network).unwrap()
+        self.addresses.get(&network).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn lookup_nodes(&self, network: Network) -> &[BootNode] {
+        self.nodes.get(&network).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn filter(&self, network: Network, filter: BootNodeFilter) -> Vec<&BootNode> {
+        self.lookup_nodes(network)
+            .iter()
+            .filter(|node| node.matches(filter))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tentacle::secio::SecioKeyPair;
+
+    use super::{
+        load_raw_bootnodes_from_config, BootNode, BootNodeFilter, BootNodes, Host, Transport,
+    };
+    use crate::{blockchain::Network, error::Error};
+
+    fn random_peer_id() -> String {
+        SecioKeyPair::secp256k1_generated()
+            .public_key()
+            .peer_id()
+            .to_base58()
+    }
+
+    fn parse(address: &str) -> BootNode {
+        format!("{}/p2p/{}", address, random_peer_id())
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn load_bundled() {
+        let bootnodes = BootNodes::initialize().unwrap();
+        assert!(!bootnodes.lookup_nodes(Network::Mainnet).is_empty());
+        assert!(!bootnodes.lookup_nodes(Network::Testnet).is_empty());
+    }
+
+    #[test]
+    fn parse_addresses() {
+        let node = parse("/ip4/1.2.3.4/tcp/8115");
+        assert_eq!(node.host(), &Host::Ip4("1.2.3.4".parse().unwrap()));
+        assert_eq!(node.port(), 8115);
+        assert_eq!(*node.transport(), Transport::Tcp);
+        let node = parse("/ip6/::1/tcp/8116");
+        assert_eq!(node.host(), &Host::Ip6("::1".parse().unwrap()));
+        assert_eq!(node.port(), 8116);
+        let node = parse("/dns4/bootnode.example.com/tcp/8115");
+        assert_eq!(node.host(), &Host::Dns4("bootnode.example.com".to_owned()));
+        let node = parse("/ip4/1.2.3.4/tcp/443/ws");
+        assert_eq!(*node.transport(), Transport::Ws);
+        assert_eq!(node.port(), 443);
+    }
+
+    #[test]
+    fn reject_invalid_addresses() {
+        let result = "/ip4/1.2.3.4/tcp/8115".parse::<BootNode>();
+        assert!(matches!(
+            result,
+            Err(Error::InvalidBootNode(_, "the peer id is missing"))
+        ));
+        let address = format!("/ip4/1.2.3.4/tcp/8115/tcp/8116/p2p/{}", random_peer_id());
+        let result = address.parse::<BootNode>();
+        assert!(matches!(
+            result,
+            Err(Error::InvalidBootNode(_, "unsupported protocol"))
+        ));
+    }
+
+    #[test]
+    fn filter_bootnodes() {
+        let nodes = vec![
+            parse("/ip4/1.2.3.4/tcp/8115"),
+            parse("/ip6/::1/tcp/8115"),
+            parse("/dns4/bootnode.example.com/tcp/8115"),
+            parse("/ip4/1.2.3.4/tcp/443/ws"),
+        ];
+        let mut bootnodes = BootNodes::default();
+        bootnodes.insert(Network::Develop, nodes);
+        let count = |filter| bootnodes.filter(Network::Develop, filter).len();
+        assert_eq!(count(BootNodeFilter::Ipv4Only), 2);
+        assert_eq!(count(BootNodeFilter::Ipv6Only), 1);
+        assert_eq!(count(BootNodeFilter::DnsOnly), 1);
+        assert_eq!(count(BootNodeFilter::TcpOnly), 3);
+        assert_eq!(count(BootNodeFilter::WsOnly), 1);
+        assert!(bootnodes
+            .filter(Network::Mainnet, BootNodeFilter::TcpOnly)
+            .is_empty());
+    }
+
+    #[test]
+    fn user_config_without_bootnodes() {
+        let result = load_raw_bootnodes_from_config("[network]\nlisten_addresses = []\n", "a.toml");
+        match result {
+            Err(Error::InvalidConfig(source, _)) => assert_eq!(source, "a.toml"),
+            _ => panic!("should be an invalid config"),
+        }
+        let result = load_raw_bootnodes_from_config("[rpc]\n", "b.toml");
+        assert!(matches!(result, Err(Error::InvalidConfig(_, _))));
+    }
+
+    #[test]
+    fn user_config_with_bootnodes() {
+        let content = "[network]\nbootnodes = [\"/ip4/127.0.0.1/tcp/8115\"]\n";
+        let raw = load_raw_bootnodes_from_config(content, "c.toml").unwrap();
+        assert_eq!(raw, vec!["/ip4/127.0.0.1/tcp/8115".to_owned()]);
     }
 }
@@ -0,0 +1,230 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use ckb_resource::{Resource, Template, TemplateContext, DEFAULT_P2P_PORT, DEFAULT_RPC_PORT};
+use property::Property;
+
+use ckb_types::bytes::Bytes;
+
+use crate::{
+    blockchain::{Bundled, Network},
+    error::{Error, Result},
+    system_deps::SystemDeps,
+};
+
+#[derive(Property, Debug, Clone)]
+#[property(get(public), set(disable), mut(disable))]
+pub struct BlockAssembler {
+    bundled: Bundled,
+    args: Bytes,
+    message: Bytes,
+}
+
+#[derive(Property, Debug, Clone)]
+#[property(get(public), set(disable), mut(disable))]
+pub struct ConfigRenderer {
+    network: Network,
+    rpc_port: u16,
+    p2p_port: u16,
+    log_to_file: bool,
+    log_to_stdout: bool,
+    block_assembler: Option<BlockAssembler>,
+}
+
+fn to_hex(data: &[u8]) -> String {
+    let hex = data
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    format!("0x{}", hex)
+}
+
+fn render(resource: &Resource, context: &TemplateContext) -> Result<String> {
+    let content = resource.get()?.into_owned();
+    let content = String::from_utf8(content).map_err(|err| {
+        Error::Unreachable(format!("bundled config {} is not utf-8: {}", resource, err))
+    })?;
+    let mut output = Vec::new();
+    Template::new(content).render_to(&mut output, context)?;
+    String::from_utf8(output).map_err(|err| {
+        Error::Unreachable(format!(
+            "rendered config {} is not utf-8: {}",
+            resource, err
+        ))
+    })
+}
+
+fn parse_default_port(port: &str) -> u16 {
+    port.parse()
+        .unwrap_or_else(|_| panic!("the default port '{}' in ckb-resource is invalid", port))
+}
+
+impl BlockAssembler {
+    /// Creates a block assembler, the bundled system cell should be a lock script.
+    pub fn new(bundled: Bundled, args: Bytes) -> Result<Self> {
+        match bundled {
+            Bundled::Secp256k1Blake160 | Bundled::Secp256k1Blake160MultiSig => Ok(Self {
+                bundled,
+                args,
+                message: Bytes::new(),
+            }),
+            Bundled::Secp256k1Data | Bundled::Dao => Err(Error::InvalidBlockAssembler(bundled)),
+        }
+    }
+
+    pub fn with_message(mut self, message: Bytes) -> Self {
+        self.message = message;
+        self
+    }
+
+    fn render(&self, network: Network) -> Result<String> {
+        let cell = SystemDeps::read().lookup_system_cell(network, self.bundled)?;
+        let (code_hash, hash_type) = if let Some(type_hash) = cell.type_hash() {
+            (type_hash, "type")
+        } else {
+            (cell.data_hash(), "data")
+        };
+        Ok(format!(
+            "[block_assembler]\n\
+             code_hash = \"{:#x}\"\n\
+             args = \"{}\"\n\
+             hash_type = \"{}\"\n\
+             message = \"{}\"",
+            code_hash,
+            to_hex(&self.args),
+            hash_type,
+            to_hex(&self.message),
+        ))
+    }
+}
+
+impl ConfigRenderer {
+    pub fn new(network: Network) -> Self {
+        Self {
+            network,
+            rpc_port: parse_default_port(DEFAULT_RPC_PORT),
+            p2p_port: parse_default_port(DEFAULT_P2P_PORT),
+            log_to_file: true,
+            log_to_stdout: true,
+            block_assembler: None,
+        }
+    }
+
+    pub fn with_rpc_port(mut self, rpc_port: u16) -> Self {
+        self.rpc_port = rpc_port;
+        self
+    }
+
+    pub fn with_p2p_port(mut self, p2p_port: u16) -> Self {
+        self.p2p_port = p2p_port;
+        self
+    }
+
+    pub fn with_log_to_file(mut self, log_to_file: bool) -> Self {
+        self.log_to_file = log_to_file;
+        self
+    }
+
+    pub fn with_log_to_stdout(mut self, log_to_stdout: bool) -> Self {
+        self.log_to_stdout = log_to_stdout;
+        self
+    }
+
+    pub fn with_block_assembler(mut self, block_assembler: BlockAssembler) -> Self {
+        self.block_assembler = Some(block_assembler);
+        self
+    }
+
+    /// Renders the content of `ckb.toml`.
+    pub fn render_ckb_config(&self) -> Result<String> {
+        self.render_resource(&Resource::bundled_ckb_config())
+    }
+
+    /// Renders the content of `ckb-miner.toml`.
+    pub fn render_miner_config(&self) -> Result<String> {
+        self.render_resource(&Resource::bundled_miner_config())
+    }
+
+    fn render_resource(&self, resource: &Resource) -> Result<String> {
+        let rpc_port = self.rpc_port.to_string();
+        let p2p_port = self.p2p_port.to_string();
+        let log_to_file = self.log_to_file.to_string();
+        let log_to_stdout = self.log_to_stdout.to_string();
+        let block_assembler = if let Some(ref block_assembler) = self.block_assembler {
+            block_assembler.render(self.network)?
+        } else {
+            String::new()
+        };
+        let context = TemplateContext::new(
+            self.network.bundled_spec_name(),
+            vec![
+                ("spec_source", "bundled"),
+                ("rpc_port", rpc_port.as_str()),
+                ("p2p_port", p2p_port.as_str()),
+                ("log_to_file", log_to_file.as_str()),
+                ("log_to_stdout", log_to_stdout.as_str()),
+                ("block_assembler", block_assembler.as_str()),
+            ],
+        );
+        render(resource, &context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ckb_types::bytes::Bytes;
+
+    use super::{BlockAssembler, ConfigRenderer};
+    use crate::{
+        blockchain::{Bundled, Network},
+        error::Error,
+        system_deps::SystemDeps,
+    };
+
+    #[test]
+    fn render_ports_and_block_assembler() {
+        let block_assembler =
+            BlockAssembler::new(Bundled::Secp256k1Blake160, Bytes::from(vec![0x11; 20])).unwrap();
+        let config = ConfigRenderer::new(Network::Testnet)
+            .with_rpc_port(18114)
+            .with_p2p_port(18115)
+            .with_block_assembler(block_assembler)
+            .render_ckb_config()
+            .unwrap();
+        let code_hash = SystemDeps::read()
+            .lookup_system_cell(Network::Testnet, Bundled::Secp256k1Blake160)
+            .unwrap()
+            .type_hash()
+            .clone()
+            .unwrap();
+        assert!(config.contains(":18114"));
+        assert!(config.contains("/tcp/18115"));
+        assert!(config.contains("[block_assembler]"));
+        assert!(config.contains(&format!("code_hash = \"{:#x}\"", code_hash)));
+        assert!(config.contains(&format!("args = \"0x{}\"", "11".repeat(20))));
+        assert!(config.contains("hash_type = \"type\""));
+    }
+
+    #[test]
+    fn render_default_ports() {
+        let renderer = ConfigRenderer::new(Network::Mainnet);
+        assert_eq!(renderer.rpc_port(), 8114);
+        assert_eq!(renderer.p2p_port(), 8115);
+        let config = renderer.render_ckb_config().unwrap();
+        assert!(config.contains(":8114"));
+        assert!(config.contains("/tcp/8115"));
+    }
+
+    #[test]
+    fn reject_non_lock_block_assembler() {
+        for bundled in &[Bundled::Secp256k1Data, Bundled::Dao] {
+            let result = BlockAssembler::new(*bundled, Bytes::new());
+            assert!(matches!(result, Err(Error::InvalidBlockAssembler(_))));
+        }
+    }
+}
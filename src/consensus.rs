@@ -0,0 +1,157 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::HashMap;
+
+use property::Property;
+use serde_derive::Deserialize;
+
+use ckb_types::{
+    core::{Capacity, EpochNumberWithFraction, Ratio, RationalU256},
+    H256, U256,
+};
+
+use crate::{blockchain::Network, error::Result};
+
+lazy_static::lazy_static! {
+    static ref CONSENSUS_PARAMS: ConsensusParams = ConsensusParams::initialize().unwrap();
+}
+
+#[derive(Deserialize)]
+struct RawConsensus {
+    id: String,
+    genesis_hash: H256,
+    genesis_epoch_length: u64,
+    epoch_duration_target: u64,
+    max_block_cycles: u64,
+    max_block_bytes: u64,
+    max_uncles_num: usize,
+    orphan_rate_target_numer: u64,
+    orphan_rate_target_denom: u64,
+    proposer_reward_ratio_numer: u64,
+    proposer_reward_ratio_denom: u64,
+    max_block_proposals_limit: u64,
+    cellbase_maturity: u64,
+    median_time_block_count: usize,
+    tx_proposal_window_closest: u64,
+    tx_proposal_window_farthest: u64,
+    primary_epoch_reward_halving_interval: u64,
+    initial_primary_epoch_reward: u64,
+    secondary_epoch_reward: u64,
+    block_version: u32,
+    tx_version: u32,
+    type_id_code_hash: H256,
+    permanent_difficulty_in_dummy: bool,
+}
+
+pub struct ConsensusParams(HashMap<Network, Consensus>);
+
+#[derive(Property, Debug, Clone)]
+#[property(get(public), set(disable), mut(disable))]
+pub struct Consensus {
+    id: String,
+    genesis_hash: H256,
+    genesis_epoch_length: u64,
+    epoch_duration_target: u64,
+    max_block_cycles: u64,
+    max_block_bytes: u64,
+    max_uncles_num: usize,
+    orphan_rate_target: RationalU256,
+    proposer_reward_ratio: Ratio,
+    max_block_proposals_limit: u64,
+    cellbase_maturity: EpochNumberWithFraction,
+    median_time_block_count: usize,
+    tx_proposal_window_closest: u64,
+    tx_proposal_window_farthest: u64,
+    primary_epoch_reward_halving_interval: u64,
+    initial_primary_epoch_reward: Capacity,
+    secondary_epoch_reward: Capacity,
+    block_version: u32,
+    tx_version: u32,
+    type_id_code_hash: H256,
+    permanent_difficulty_in_dummy: bool,
+}
+
+impl From<RawConsensus> for Consensus {
+    fn from(raw: RawConsensus) -> Self {
+        let RawConsensus {
+            id,
+            genesis_hash,
+            genesis_epoch_length,
+            epoch_duration_target,
+            max_block_cycles,
+            max_block_bytes,
+            max_uncles_num,
+            orphan_rate_target_numer,
+            orphan_rate_target_denom,
+            proposer_reward_ratio_numer,
+            proposer_reward_ratio_denom,
+            max_block_proposals_limit,
+            cellbase_maturity,
+            median_time_block_count,
+            tx_proposal_window_closest,
+            tx_proposal_window_farthest,
+            primary_epoch_reward_halving_interval,
+            initial_primary_epoch_reward,
+            secondary_epoch_reward,
+            block_version,
+            tx_version,
+            type_id_code_hash,
+            permanent_difficulty_in_dummy,
+        } = raw;
+        Self {
+            id,
+            genesis_hash,
+            genesis_epoch_length,
+            epoch_duration_target,
+            max_block_cycles,
+            max_block_bytes,
+            max_uncles_num,
+            orphan_rate_target: RationalU256::new_raw(
+                U256::from(orphan_rate_target_numer),
+                U256::from(orphan_rate_target_denom),
+            ),
+            proposer_reward_ratio: Ratio(proposer_reward_ratio_numer, proposer_reward_ratio_denom),
+            max_block_proposals_limit,
+            cellbase_maturity: EpochNumberWithFraction::from_full_value(cellbase_maturity),
+            median_time_block_count,
+            tx_proposal_window_closest,
+            tx_proposal_window_farthest,
+            primary_epoch_reward_halving_interval,
+            initial_primary_epoch_reward: Capacity::shannons(initial_primary_epoch_reward),
+            secondary_epoch_reward: Capacity::shannons(secondary_epoch_reward),
+            block_version,
+            tx_version,
+            type_id_code_hash,
+            permanent_difficulty_in_dummy,
+        }
+    }
+}
+
+impl ConsensusParams {
+    pub fn read() -> &'static Self {
+        &CONSENSUS_PARAMS
+    }
+
+    fn initialize() -> Result<Self> {
+        let raw_consensus: HashMap<String, RawConsensus> =
+            toml::from_str(include_str!(concat!(env!("OUT_DIR"), "/consensus.toml")))?;
+        raw_consensus
+            .into_iter()
+            .map(|(spec_name, raw)| {
+                let network = Network::from_spec_name(&spec_name)?;
+                Ok((network, raw.into()))
+            })
+            .collect::<Result<HashMap<_, _>>>()
+            .map(Self)
+    }
+
+    pub fn lookup(&self, network: Network) -> &Consensus {
+        self.0.get(&network).unwrap()
+    }
+}
@@ -0,0 +1,408 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+mod field;
+
+pub use self::field::{maximum_withdraw, DaoField};
+
+use property::Property;
+
+use ckb_types::{
+    bytes::Bytes,
+    core::{Capacity, EpochNumberWithFraction, HeaderView, ScriptHashType},
+    packed,
+    prelude::*,
+};
+
+use crate::{
+    blockchain::{Bundled, Network},
+    error::{Error, Result},
+    since::Since,
+    system_deps::SystemDeps,
+};
+
+/// The count of epochs which a deposit is locked in one cycle.
+pub const LOCK_PERIOD_EPOCHS: u64 = 180;
+
+/// Builds transactions for the three phases of the Nervos DAO: deposit, prepare and withdraw.
+///
+/// Only inputs, outputs, header deps, cell deps and DAO witnesses are filled, change outputs
+/// and signatures should be added by the callers.
+#[derive(Property, Debug, Clone)]
+#[property(get(public), set(disable), mut(disable))]
+pub struct DaoBuilder {
+    type_script: packed::Script,
+    cell_deps: Vec<packed::CellDep>,
+}
+
+/// A cell which was deposited into the Nervos DAO.
+#[derive(Property, Debug, Clone)]
+#[property(get(public), set(disable), mut(disable))]
+pub struct DepositedCell {
+    out_point: packed::OutPoint,
+    output: packed::CellOutput,
+    header: HeaderView,
+}
+
+/// A cell which was prepared to withdraw from the Nervos DAO.
+#[derive(Property, Debug, Clone)]
+#[property(get(public), set(disable), mut(disable))]
+pub struct PreparedCell {
+    out_point: packed::OutPoint,
+    deposit_header: HeaderView,
+    prepare_header: HeaderView,
+}
+
+impl DepositedCell {
+    pub fn new(
+        out_point: packed::OutPoint,
+        output: packed::CellOutput,
+        header: HeaderView,
+    ) -> Self {
+        Self {
+            out_point,
+            output,
+            header,
+        }
+    }
+}
+
+impl PreparedCell {
+    pub fn new(
+        out_point: packed::OutPoint,
+        deposit_header: HeaderView,
+        prepare_header: HeaderView,
+    ) -> Self {
+        Self {
+            out_point,
+            deposit_header,
+            prepare_header,
+        }
+    }
+}
+
+/// Calculates the minimal since for withdrawing a deposit, which is an absolute epoch.
+///
+/// A deposit could only be withdrawn at the end of a lock period, which starts from the
+/// deposit epoch and lasts for a multiple of `LOCK_PERIOD_EPOCHS` epochs.
+///
+/// The prepare epoch should not be earlier than the deposit epoch.
+pub fn minimal_withdraw_since(
+    deposit: EpochNumberWithFraction,
+    prepare: EpochNumberWithFraction,
+) -> Result<Since> {
+    let deposit_fraction = deposit.index() * prepare.length();
+    let prepare_fraction = prepare.index() * deposit.length();
+    if prepare.number() < deposit.number()
+        || (prepare.number() == deposit.number() && prepare_fraction < deposit_fraction)
+    {
+        return Err(Error::InvalidDaoHeaders(format!(
+            "the prepare epoch {} is earlier than the deposit epoch {}",
+            prepare, deposit
+        )));
+    }
+    let mut deposited_epochs = prepare.number() - deposit.number();
+    if prepare_fraction > deposit_fraction {
+        deposited_epochs += 1;
+    }
+    let lock_epochs =
+        (deposited_epochs + (LOCK_PERIOD_EPOCHS - 1)) / LOCK_PERIOD_EPOCHS * LOCK_PERIOD_EPOCHS;
+    let minimal = EpochNumberWithFraction::new(
+        deposit.number() + lock_epochs,
+        deposit.index(),
+        deposit.length(),
+    );
+    Ok(Since::AbsoluteEpoch(minimal))
+}
+
+fn push_unique(header_deps: &mut Vec<packed::Byte32>, hash: packed::Byte32) -> usize {
+    if let Some(index) = header_deps.iter().position(|h| h == &hash) {
+        index
+    } else {
+        header_deps.push(hash);
+        header_deps.len() - 1
+    }
+}
+
+impl DaoBuilder {
+    /// Creates a builder for a network, the cell deps include the DAO script and the system
+    /// lock scripts which are used by the inputs.
+    pub fn new(network: Network, locks: &[Bundled]) -> Result<Self> {
+        let system_deps = SystemDeps::read();
+        let dao = system_deps.lookup_system_cell(network, Bundled::Dao)?;
+        let type_hash = dao
+            .type_hash()
+            .clone()
+            .ok_or_else(|| Error::Unreachable(format!("DAO in {} has no type hash", network)))?;
+        let type_script = packed::Script::new_builder()
+            .code_hash(type_hash.pack())
+            .hash_type(ScriptHashType::Type.into())
+            .build();
+        let mut scripts = vec![Bundled::Dao];
+        scripts.extend_from_slice(locks);
+        let cell_deps = system_deps.plan_cell_deps(network, &scripts)?;
+        Ok(Self {
+            type_script,
+            cell_deps,
+        })
+    }
+
+    /// Builds a transaction which deposits capacities into the Nervos DAO.
+    pub fn deposit(
+        &self,
+        inputs: &[packed::CellInput],
+        deposits: &[(packed::Script, Capacity)],
+    ) -> packed::Transaction {
+        let outputs = deposits
+            .iter()
+            .map(|(lock, capacity)| {
+                packed::CellOutput::new_builder()
+                    .capacity(capacity.pack())
+                    .lock(lock.clone())
+                    .type_(Some(self.type_script.clone()).pack())
+                    .build()
+            })
+            .collect::<Vec<_>>();
+        let outputs_data = deposits
+            .iter()
+            .map(|_| Bytes::from(vec![0u8; 8]).pack())
+            .collect::<Vec<packed::Bytes>>();
+        let raw_tx = packed::RawTransaction::new_builder()
+            .cell_deps(self.cell_deps.clone().pack())
+            .inputs(inputs.to_vec().pack())
+            .outputs(outputs.pack())
+            .outputs_data(outputs_data.pack())
+            .build();
+        packed::Transaction::new_builder().raw(raw_tx).build()
+    }
+
+    /// Builds a transaction which prepares deposited cells for withdrawing, a.k.a. the phase 1
+    /// of withdrawing.
+    ///
+    /// Each deposited cell is converted into a cell with the same capacity, lock and type, and
+    /// the data is the number of the deposit block.
+    pub fn prepare(&self, deposits: &[DepositedCell]) -> packed::Transaction {
+        let mut header_deps = Vec::new();
+        let mut inputs = Vec::with_capacity(deposits.len());
+        let mut outputs = Vec::with_capacity(deposits.len());
+        let mut outputs_data = Vec::with_capacity(deposits.len());
+        for deposit in deposits {
+            push_unique(&mut header_deps, deposit.header.hash());
+            let input = packed::CellInput::new(deposit.out_point.clone(), 0);
+            let data = Bytes::from(deposit.header.number().to_le_bytes().to_vec());
+            inputs.push(input);
+            outputs.push(deposit.output.clone());
+            outputs_data.push(data.pack());
+        }
+        let raw_tx = packed::RawTransaction::new_builder()
+            .cell_deps(self.cell_deps.clone().pack())
+            .header_deps(header_deps.pack())
+            .inputs(inputs.pack())
+            .outputs(outputs.pack())
+            .outputs_data(outputs_data.pack())
+            .build();
+        packed::Transaction::new_builder().raw(raw_tx).build()
+    }
+
+    /// Builds a transaction which withdraws prepared cells, a.k.a. the phase 2 of withdrawing.
+    ///
+    /// The outputs are provided by the callers, the maximum withdrawable capacity could be
+    /// calculated by `maximum_withdraw` from the deposit header and the prepare header.
+    pub fn withdraw(
+        &self,
+        prepared: &[PreparedCell],
+        outputs: &[(packed::CellOutput, Bytes)],
+    ) -> Result<packed::Transaction> {
+        let mut header_deps = Vec::new();
+        let mut inputs = Vec::with_capacity(prepared.len());
+        let mut witnesses = Vec::with_capacity(prepared.len());
+        for cell in prepared {
+            let deposit_index = push_unique(&mut header_deps, cell.deposit_header.hash());
+            push_unique(&mut header_deps, cell.prepare_header.hash());
+            let since =
+                minimal_withdraw_since(cell.deposit_header.epoch(), cell.prepare_header.epoch())?;
+            let input = packed::CellInput::new(cell.out_point.clone(), since.encode()?);
+            let input_type = Bytes::from((deposit_index as u64).to_le_bytes().to_vec());
+            let witness = packed::WitnessArgs::new_builder()
+                .input_type(Some(input_type).pack())
+                .build()
+                .as_bytes()
+                .pack();
+            inputs.push(input);
+            witnesses.push(witness);
+        }
+        let (outputs, outputs_data): (Vec<_>, Vec<_>) = outputs
+            .iter()
+            .map(|(output, data)| (output.clone(), data.pack()))
+            .unzip();
+        let raw_tx = packed::RawTransaction::new_builder()
+            .cell_deps(self.cell_deps.clone().pack())
+            .header_deps(header_deps.pack())
+            .inputs(inputs.pack())
+            .outputs(outputs.pack())
+            .outputs_data(outputs_data.pack())
+            .build();
+        let tx = packed::Transaction::new_builder()
+            .raw(raw_tx)
+            .witnesses(witnesses.pack())
+            .build();
+        Ok(tx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ckb_types::{
+        bytes::Bytes,
+        core::{Capacity, EpochNumberWithFraction, HeaderBuilder, HeaderView},
+        packed,
+        prelude::*,
+    };
+
+    use super::{minimal_withdraw_since, DaoBuilder, DepositedCell, PreparedCell};
+    use crate::{
+        blockchain::{Bundled, Network},
+        error::Error,
+        since::Since,
+    };
+
+    fn epoch(number: u64, index: u64, length: u64) -> EpochNumberWithFraction {
+        EpochNumberWithFraction::new(number, index, length)
+    }
+
+    fn header(number: u64, epoch: EpochNumberWithFraction) -> HeaderView {
+        HeaderBuilder::default()
+            .number(number.pack())
+            .epoch(epoch.full_value().pack())
+            .build()
+    }
+
+    fn out_point(index: u32) -> packed::OutPoint {
+        packed::OutPoint::new(packed::Byte32::default(), index)
+    }
+
+    fn builder() -> DaoBuilder {
+        DaoBuilder::new(Network::Mainnet, &[Bundled::Secp256k1Blake160]).unwrap()
+    }
+
+    #[test]
+    fn withdraw_since() {
+        let deposit = epoch(5, 100, 1000);
+        let cases = [
+            // Exactly 180 epochs.
+            (epoch(185, 100, 1000), epoch(185, 100, 1000)),
+            // Just past 180 epochs.
+            (epoch(185, 101, 1000), epoch(365, 100, 1000)),
+            // The same fraction in different lengths.
+            (epoch(100, 50, 500), epoch(185, 100, 1000)),
+            (epoch(5, 101, 1000), epoch(185, 100, 1000)),
+            (epoch(364, 0, 1800), epoch(365, 100, 1000)),
+        ];
+        for (prepare, expected) in cases.iter() {
+            let since = minimal_withdraw_since(deposit, *prepare).unwrap();
+            assert_eq!(since, Since::AbsoluteEpoch(*expected), "{}", prepare);
+        }
+    }
+
+    #[test]
+    fn withdraw_since_in_wrong_order() {
+        let deposit = epoch(5, 100, 1000);
+        for prepare in &[epoch(4, 999, 1000), epoch(5, 99, 1000), epoch(5, 49, 500)] {
+            let result = minimal_withdraw_since(deposit, *prepare);
+            assert!(matches!(result, Err(Error::InvalidDaoHeaders(_))));
+        }
+    }
+
+    #[test]
+    fn deposit_and_prepare() {
+        let builder = builder();
+        let lock = packed::Script::default();
+        let capacity = Capacity::shannons(100_000_000_000);
+        let input = packed::CellInput::new(out_point(0), 0);
+        let deposits = vec![(lock.clone(), capacity); 2];
+        let tx = builder.deposit(&[input], &deposits);
+        assert_eq!(tx.raw().cell_deps().len(), builder.cell_deps().len());
+        assert_eq!(tx.raw().outputs().len(), 2);
+        for (output, data) in tx.raw().outputs().into_iter().zip(tx.raw().outputs_data()) {
+            assert_eq!(output.type_().to_opt(), Some(builder.type_script().clone()));
+            assert_eq!(output.lock(), lock);
+            assert_eq!(data.raw_data(), Bytes::from(vec![0u8; 8]));
+        }
+        let deposit_header = header(1024, epoch(5, 100, 1000));
+        let deposits = tx
+            .raw()
+            .outputs()
+            .into_iter()
+            .enumerate()
+            .map(|(index, output)| {
+                let out_point = packed::OutPoint::new(tx.calc_tx_hash(), index as u32);
+                DepositedCell::new(out_point, output, deposit_header.clone())
+            })
+            .collect::<Vec<_>>();
+        let tx = builder.prepare(&deposits);
+        assert_eq!(tx.raw().header_deps().len(), 1);
+        assert_eq!(tx.raw().inputs().len(), 2);
+        for data in tx.raw().outputs_data() {
+            assert_eq!(data.raw_data(), Bytes::from(1024u64.to_le_bytes().to_vec()));
+        }
+    }
+
+    #[test]
+    fn withdraw_with_shared_headers() {
+        let deposit_a = header(1024, epoch(5, 100, 1000));
+        let deposit_b = header(2048, epoch(6, 100, 1000));
+        let prepare = header(50_000, epoch(190, 0, 1000));
+        let prepared = vec![
+            PreparedCell::new(out_point(0), deposit_a.clone(), prepare.clone()),
+            PreparedCell::new(out_point(1), deposit_a.clone(), prepare.clone()),
+            PreparedCell::new(out_point(2), deposit_b.clone(), prepare.clone()),
+        ];
+        let tx = builder().withdraw(&prepared, &[]).unwrap();
+        let header_deps = tx.raw().header_deps().into_iter().collect::<Vec<_>>();
+        assert_eq!(
+            header_deps,
+            vec![deposit_a.hash(), prepare.hash(), deposit_b.hash()]
+        );
+        let deposit_indexes = tx
+            .witnesses()
+            .into_iter()
+            .map(|witness| {
+                let witness = packed::WitnessArgs::from_slice(&witness.raw_data()).unwrap();
+                let input_type = witness.input_type().to_opt().unwrap().raw_data();
+                let mut index = [0u8; 8];
+                index.copy_from_slice(&input_type);
+                u64::from_le_bytes(index)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(deposit_indexes, vec![0, 0, 2]);
+        let sinces = tx
+            .raw()
+            .inputs()
+            .into_iter()
+            .map(|input| Since::decode(input.since().unpack()).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sinces,
+            vec![
+                Since::AbsoluteEpoch(epoch(365, 100, 1000)),
+                Since::AbsoluteEpoch(epoch(365, 100, 1000)),
+                Since::AbsoluteEpoch(epoch(366, 100, 1000)),
+            ]
+        );
+    }
+
+    #[test]
+    fn withdraw_with_headers_in_wrong_order() {
+        let deposit = header(1024, epoch(5, 100, 1000));
+        let prepare = header(50_000, epoch(190, 0, 1000));
+        // The deposit header and the prepare header are swapped.
+        let prepared = [PreparedCell::new(out_point(0), prepare, deposit)];
+        let result = builder().withdraw(&prepared, &[]);
+        assert!(matches!(result, Err(Error::InvalidDaoHeaders(_))));
+    }
+}
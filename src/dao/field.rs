@@ -0,0 +1,203 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use property::Property;
+
+use ckb_types::{
+    core::{Capacity, HeaderView},
+    packed,
+    prelude::*,
+};
+
+use crate::error::{Error, Result};
+
+/// The decoded `dao` field in a block header.
+#[derive(Property, Debug, Clone, Copy, PartialEq, Eq)]
+#[property(get(public), set(disable), mut(disable))]
+pub struct DaoField {
+    /// The total issuance.
+    c: Capacity,
+    /// The accumulated rate, the initial value is `10 ^ 16`.
+    ar: u64,
+    /// The total unissued secondary issuance.
+    s: Capacity,
+    /// The total occupied capacities.
+    u: Capacity,
+}
+
+fn read_u64(data: &[u8], start: usize) -> u64 {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(&data[start..start + 8]);
+    u64::from_le_bytes(buf)
+}
+
+impl From<&packed::Byte32> for DaoField {
+    fn from(dao: &packed::Byte32) -> Self {
+        let data = dao.raw_data();
+        Self {
+            c: Capacity::shannons(read_u64(&data, 0)),
+            ar: read_u64(&data, 8),
+            s: Capacity::shannons(read_u64(&data, 16)),
+            u: Capacity::shannons(read_u64(&data, 24)),
+        }
+    }
+}
+
+impl DaoField {
+    pub fn from_header(header: &HeaderView) -> Self {
+        Self::from(&header.dao())
+    }
+
+    pub fn pack(&self) -> packed::Byte32 {
+        let mut data = [0u8; 32];
+        data[0..8].copy_from_slice(&self.c.as_u64().to_le_bytes());
+        data[8..16].copy_from_slice(&self.ar.to_le_bytes());
+        data[16..24].copy_from_slice(&self.s.as_u64().to_le_bytes());
+        data[24..32].copy_from_slice(&self.u.as_u64().to_le_bytes());
+        data.pack()
+    }
+}
+
+/// Calculates the maximum withdrawable capacity of a deposited cell.
+///
+/// Only the counted capacity, which is the capacity minus the occupied capacity, earns the
+/// interests, which is in proportion to the growth of the accumulated rate between the deposit
+/// header and the withdraw header.
+pub fn maximum_withdraw(
+    output: &packed::CellOutput,
+    data_len: usize,
+    deposit_header: &HeaderView,
+    withdraw_header: &HeaderView,
+) -> Result<Capacity> {
+    let capacity_error = |err: &dyn ::std::fmt::Display| Error::Capacity(err.to_string());
+    let deposit_ar = DaoField::from_header(deposit_header).ar;
+    let withdraw_ar = DaoField::from_header(withdraw_header).ar;
+    if deposit_ar == 0 {
+        return Err(Error::Capacity(
+            "the accumulated rate of the deposit header is zero".to_owned(),
+        ));
+    }
+    let data_capacity = Capacity::bytes(data_len).map_err(|err| capacity_error(&err))?;
+    let occupied = output
+        .occupied_capacity(data_capacity)
+        .map_err(|err| capacity_error(&err))?;
+    let capacity: Capacity = output.capacity().unpack();
+    let counted = capacity
+        .safe_sub(occupied)
+        .map_err(|err| capacity_error(&err))?;
+    let withdraw_counted =
+        u128::from(counted.as_u64()) * u128::from(withdraw_ar) / u128::from(deposit_ar);
+    if withdraw_counted > u128::from(u64::max_value()) {
+        return Err(Error::Capacity(
+            "the withdrawable capacity is overflow".to_owned(),
+        ));
+    }
+    Capacity::shannons(withdraw_counted as u64)
+        .safe_add(occupied)
+        .map_err(|err| capacity_error(&err))
+}
+
+#[cfg(test)]
+mod tests {
+    use ckb_types::{
+        bytes::Bytes,
+        core::{Capacity, HeaderBuilder, HeaderView, ScriptHashType},
+        h256, packed,
+        prelude::*,
+    };
+
+    use super::{maximum_withdraw, DaoField};
+    use crate::error::Error;
+    #[cfg(any(feature = "verifier", feature = "test-support"))]
+    use crate::{blockchain::Network, genesis::Genesis};
+
+    /// The `dao` field in the header of the mainnet genesis block.
+    const MAINNET_GENESIS_DAO: [u8; 32] =
+        h256!("0x8874337e541ea12e0000c16ff286230029bfa3320800000000710b00c0fefe06").0;
+
+    fn header_with_ar(ar: u64) -> HeaderView {
+        let genesis = DaoField::from(&MAINNET_GENESIS_DAO.pack());
+        let dao = DaoField { ar, ..genesis };
+        HeaderBuilder::default().dao(dao.pack()).build()
+    }
+
+    fn deposited_output(capacity: Capacity) -> packed::CellOutput {
+        let lock = packed::Script::new_builder()
+            .hash_type(ScriptHashType::Type.into())
+            .args(Bytes::from(vec![0u8; 20]).pack())
+            .build();
+        let type_ = packed::Script::new_builder()
+            .hash_type(ScriptHashType::Type.into())
+            .build();
+        packed::CellOutput::new_builder()
+            .capacity(capacity.pack())
+            .lock(lock)
+            .type_(Some(type_).pack())
+            .build()
+    }
+
+    #[test]
+    fn decode_mainnet_genesis() {
+        let dao = DaoField::from(&MAINNET_GENESIS_DAO.pack());
+        assert_eq!(dao.c(), &Capacity::shannons(3_360_000_145_238_488_200));
+        assert_eq!(dao.ar(), 10_000_000_000_000_000);
+        assert_eq!(dao.s(), &Capacity::shannons(35_209_330_473));
+        assert_eq!(dao.u(), &Capacity::shannons(504_120_308_900_000_000));
+        assert_eq!(dao.pack().as_slice(), &MAINNET_GENESIS_DAO[..]);
+    }
+
+    #[cfg(any(feature = "verifier", feature = "test-support"))]
+    #[test]
+    fn decode_bundled_mainnet_genesis() {
+        let header = Genesis::read().lookup(Network::Mainnet).header();
+        assert_eq!(
+            DaoField::from_header(&header),
+            DaoField::from(&MAINNET_GENESIS_DAO.pack())
+        );
+    }
+
+    #[test]
+    fn maximum_withdraw_from_genesis_rate() {
+        // The occupied capacity is 102 CKBytes, the other 898 CKBytes earn the interests.
+        let output = deposited_output(Capacity::shannons(100_000_000_000));
+        let deposit_header = header_with_ar(10_000_000_000_000_000);
+        let cases = [
+            (10_000_000_000_000_000, 100_000_000_000),
+            (10_001_000_000_000_000, 100_008_980_000),
+            (10_000_123_456_789_012, 100_001_108_641),
+        ];
+        for (withdraw_ar, expected) in cases.iter() {
+            let withdraw_header = header_with_ar(*withdraw_ar);
+            let capacity = maximum_withdraw(&output, 8, &deposit_header, &withdraw_header);
+            assert_eq!(capacity.unwrap(), Capacity::shannons(*expected));
+        }
+    }
+
+    // No mainnet header other than the genesis is bundled, so a real deposit and withdraw pair
+    // could not be checked here; the genesis header is the only real header which is used.
+    #[cfg(any(feature = "verifier", feature = "test-support"))]
+    #[test]
+    fn maximum_withdraw_in_mainnet_genesis() {
+        let header = Genesis::read().lookup(Network::Mainnet).header();
+        let output = deposited_output(Capacity::shannons(100_000_000_000));
+        let capacity = maximum_withdraw(&output, 8, &header, &header).unwrap();
+        assert_eq!(capacity, Capacity::shannons(100_000_000_000));
+        let withdraw_header = header_with_ar(DaoField::from_header(&header).ar() * 2);
+        let capacity = maximum_withdraw(&output, 8, &header, &withdraw_header).unwrap();
+        assert_eq!(capacity, Capacity::shannons(189_800_000_000));
+    }
+
+    #[test]
+    fn maximum_withdraw_with_zero_rate() {
+        let output = deposited_output(Capacity::shannons(100_000_000_000));
+        let deposit_header = header_with_ar(0);
+        let withdraw_header = header_with_ar(10_001_000_000_000_000);
+        let result = maximum_withdraw(&output, 8, &deposit_header, &withdraw_header);
+        assert!(matches!(result, Err(Error::Capacity(_))));
+    }
+}
@@ -0,0 +1,147 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Derives the spec hashes from a chain spec, it's shared with the build script.
+
+use ckb_chain_spec::{self as spec, consensus::Consensus};
+use ckb_types::{packed, prelude::*, H256};
+
+use crate::raw::{RawDepGroup, RawMemberCell, RawSpecHashes, RawSystemCell};
+
+fn verify_dep_group_members(
+    spec_name: &str,
+    included_cells: &[String],
+    members: &[RawMemberCell],
+    system_cells: &[RawSystemCell],
+) -> Result<(), String> {
+    if included_cells.len() != members.len() {
+        return Err(format!(
+            "dep group members unmatched in {}: {} included cells but {} members",
+            spec_name,
+            included_cells.len(),
+            members.len()
+        ));
+    }
+    for (path, member) in included_cells.iter().zip(members.iter()) {
+        let matched = system_cells.iter().any(|cell| {
+            &cell.path == path && cell.tx_hash == member.tx_hash && cell.index == member.index
+        });
+        if !matched {
+            return Err(format!(
+                "dep group member unmatched in {}: {} is not {:#x}#{}",
+                spec_name, path, member.tx_hash, member.index
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Builds the consensus, the genesis hash in the chain spec is checked if it's provided.
+pub(crate) fn build_consensus(chain_spec: &spec::ChainSpec) -> Result<Consensus, String> {
+    let mut chain_spec = chain_spec.clone();
+    let spec_name = &chain_spec.name;
+    let hash_option = chain_spec.genesis.hash.take();
+    let consensus = chain_spec
+        .build_consensus()
+        .map_err(|err| format!("failed to build consensus for {}: {}", spec_name, err))?;
+    if let Some(hash) = hash_option {
+        let genesis_hash: H256 = consensus.genesis_hash().unpack();
+        if hash != genesis_hash {
+            return Err(format!(
+                "Genesis hash unmatched in {} chainspec config file: in file {:#x}, actual {:#x}",
+                spec_name, hash, genesis_hash
+            ));
+        }
+    }
+    Ok(consensus)
+}
+
+/// Derives the spec hashes from the genesis block of a consensus which was built from the chain
+/// spec.
+pub(crate) fn load_hashes_from_consensus(
+    chain_spec: &spec::ChainSpec,
+    consensus: &Consensus,
+) -> Result<RawSpecHashes, String> {
+    let spec_name = &chain_spec.name;
+    let block = consensus.genesis_block();
+    let cellbase = &block.transactions()[0];
+    let dep_group_tx = &block.transactions()[1];
+
+    let cells_hashes = chain_spec
+        .genesis
+        .system_cells
+        .iter()
+        .map(|system_cell| &system_cell.file)
+        .zip(
+            cellbase
+                .outputs()
+                .into_iter()
+                .zip(cellbase.outputs_data().into_iter())
+                .skip(1),
+        )
+        .enumerate()
+        .map(|(index_minus_one, (resource, (output, data)))| {
+            let data_hash: H256 = packed::CellOutput::calc_data_hash(&data.raw_data()).unpack();
+            let type_hash: Option<H256> = output
+                .type_()
+                .to_opt()
+                .map(|script| script.calc_script_hash().unpack());
+            RawSystemCell {
+                path: resource.to_string(),
+                tx_hash: cellbase.hash().unpack(),
+                index: index_minus_one + 1,
+                data_hash,
+                type_hash,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let dep_groups = chain_spec
+        .genesis
+        .dep_groups
+        .iter()
+        .enumerate()
+        .map(|(index, dep_group)| {
+            let included_cells = dep_group
+                .files
+                .iter()
+                .map(|res| res.to_string())
+                .collect::<Vec<_>>();
+            let data = dep_group_tx
+                .outputs_data()
+                .get(index)
+                .ok_or_else(|| format!("dep group {} was not found in {}", index, spec_name))?;
+            let members = packed::OutPointVec::from_slice(&data.raw_data())
+                .map_err(|_| format!("dep group {} in {} is malformed", index, spec_name))?
+                .into_iter()
+                .map(|out_point| {
+                    let tx_hash: H256 = out_point.tx_hash().unpack();
+                    let index: u32 = out_point.index().unpack();
+                    RawMemberCell {
+                        tx_hash,
+                        index: index as usize,
+                    }
+                })
+                .collect::<Vec<_>>();
+            verify_dep_group_members(spec_name, &included_cells, &members, &cells_hashes)?;
+            Ok(RawDepGroup {
+                included_cells,
+                tx_hash: dep_group_tx.hash().unpack(),
+                index,
+                members,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(RawSpecHashes {
+        genesis: consensus.genesis_hash().unpack(),
+        cellbase: cellbase.hash().unpack(),
+        system_cells: cells_hashes,
+        dep_groups,
+    })
+}
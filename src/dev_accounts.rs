@@ -0,0 +1,152 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use property::Property;
+
+use ckb_crypto::secp::{Privkey, Pubkey};
+use ckb_types::{
+    bytes::Bytes,
+    core::{Capacity, ScriptHashType},
+    h256, packed,
+    prelude::*,
+    H256,
+};
+
+use crate::{
+    blockchain::{Bundled, Network},
+    error::{Error, Result},
+    genesis::Genesis,
+    signing::blake160,
+    system_deps::SystemDeps,
+};
+
+lazy_static::lazy_static! {
+    static ref DEV_ACCOUNTS: DevAccounts = DevAccounts::initialize().unwrap();
+}
+
+/// The private keys which the genesis cells of the bundled dev chain spec are issued to.
+///
+/// These keys are publicly documented, never use them in other networks.
+const DEV_PRIVKEYS: &[H256] = &[
+    h256!("0xd00c06bfd800d27397002dca6fb0993d5ba6399b4238b2f29ee9deb97593d2bc"),
+    h256!("0x63d86723e08f0f813a36ce6aa123bb2289d90680ae1e99d4de8cdb334553f24d"),
+];
+
+pub struct DevAccounts(Vec<DevAccount>);
+
+/// An account which owns an issued cell in the genesis block of `Network::Develop`.
+#[derive(Property, Clone)]
+#[property(get(public), set(disable), mut(disable))]
+pub struct DevAccount {
+    privkey: Privkey,
+    pubkey: Pubkey,
+    lock_args: [u8; 20],
+    lock_script: packed::Script,
+    capacity: Capacity,
+    out_point: packed::OutPoint,
+}
+
+impl DevAccounts {
+    pub fn read() -> &'static Self {
+        &DEV_ACCOUNTS
+    }
+
+    fn initialize() -> Result<Self> {
+        let network = Network::Develop;
+        let cell = SystemDeps::read().lookup_system_cell(network, Bundled::Secp256k1Blake160)?;
+        let code_hash = cell.type_hash().clone().ok_or_else(|| {
+            Error::Unreachable(format!("secp256k1 in {} has no type hash", network))
+        })?;
+        let genesis = Genesis::read().lookup(network);
+        let cellbase = &genesis.transactions()[0];
+        DEV_PRIVKEYS
+            .iter()
+            .enumerate()
+            .map(|(account_index, key)| {
+                let privkey = Privkey::from(key.clone());
+                let pubkey = privkey
+                    .pubkey()
+                    .map_err(|err| Error::Unreachable(format!("dev privkey: {}", err)))?;
+                let lock_args = blake160(&pubkey.serialize());
+                let lock_script = packed::Script::new_builder()
+                    .code_hash(code_hash.pack())
+                    .hash_type(ScriptHashType::Type.into())
+                    .args(Bytes::from(lock_args.to_vec()).pack())
+                    .build();
+                let (index, output) = cellbase
+                    .outputs()
+                    .into_iter()
+                    .enumerate()
+                    .find(|(_, output)| output.lock() == lock_script)
+                    .ok_or_else(|| {
+                        Error::Unreachable(format!(
+                            "no genesis cell was issued to dev account {}",
+                            account_index
+                        ))
+                    })?;
+                let out_point = packed::OutPoint::new(cellbase.hash(), index as u32);
+                Ok(DevAccount {
+                    privkey,
+                    pubkey,
+                    lock_args,
+                    lock_script,
+                    capacity: output.capacity().unpack(),
+                    out_point,
+                })
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(Self)
+    }
+
+    pub fn accounts(&self) -> &[DevAccount] {
+        &self.0
+    }
+
+    pub fn get(&self, index: usize) -> Option<&DevAccount> {
+        self.0.get(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ckb_types::{core::Capacity, h160, prelude::*};
+
+    use super::DevAccounts;
+    use crate::{blockchain::Network, genesis::Genesis};
+
+    #[test]
+    fn lock_args_of_dev_accounts() {
+        let lock_args = DevAccounts::read()
+            .accounts()
+            .iter()
+            .map(|account| *account.lock_args())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lock_args,
+            vec![
+                h160!("0xc8328aabcd9b9e8e64fbc566c4385c3bdeb219d7").0,
+                h160!("0x470dcdc5e44064909650113a274b3b36aecb6dc7").0,
+            ]
+        );
+    }
+
+    #[test]
+    fn out_points_of_dev_accounts() {
+        let genesis = Genesis::read();
+        for account in DevAccounts::read().accounts() {
+            let (output, _) = genesis.cell(Network::Develop, account.out_point()).unwrap();
+            assert_eq!(&output.lock(), account.lock_script());
+            let capacity: Capacity = output.capacity().unpack();
+            assert_eq!(&capacity, account.capacity());
+            assert_eq!(
+                &account.lock_script().args().raw_data()[..],
+                &account.lock_args()[..]
+            );
+        }
+    }
+}
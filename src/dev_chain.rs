@@ -0,0 +1,197 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::{convert::TryFrom, path::Path, str::FromStr};
+
+use property::Property;
+
+use ckb_chain_spec as spec;
+use ckb_resource as res;
+use ckb_types::{core::Capacity, packed};
+
+use crate::{
+    blockchain::Bundled,
+    derivation,
+    error::{Error, Result},
+    raw::RawSpecHashes,
+    system_deps::{SpecHashes, SystemCell},
+};
+
+/// Builds a chain spec for dev chains, which is based on the bundled dev chain spec.
+#[derive(Debug, Clone)]
+pub struct DevChainBuilder {
+    chain_spec: spec::ChainSpec,
+}
+
+/// A chain spec which was built by `DevChainBuilder`, with all hashes of the genesis block.
+#[derive(Property, Debug, Clone)]
+#[property(get(public), set(disable), mut(disable))]
+pub struct DevChain {
+    /// The content of the chain spec file, in TOML.
+    spec: String,
+    hashes: SpecHashes,
+    /// The system cells which are not bundled, with the paths of their files.
+    extra_cells: Vec<(String, SystemCell)>,
+}
+
+impl DevChainBuilder {
+    pub fn new() -> Result<Self> {
+        let bundled = res::Resource::bundled("specs/dev.toml".to_owned());
+        let chain_spec = spec::ChainSpec::load_from(&bundled)
+            .map_err(|err| Error::ChainSpec(err.to_string()))?;
+        Ok(Self { chain_spec })
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.chain_spec.name = name.to_owned();
+        self
+    }
+
+    /// Replaces all issued cells in the genesis block.
+    pub fn with_issued_cells(mut self, cells: &[(packed::Script, Capacity)]) -> Self {
+        self.chain_spec.genesis.issued_cells.clear();
+        for (lock, capacity) in cells {
+            self = self.with_issued_cell(lock.clone(), *capacity);
+        }
+        self
+    }
+
+    pub fn with_issued_cell(mut self, lock: packed::Script, capacity: Capacity) -> Self {
+        let issued_cell = spec::IssuedCell {
+            capacity,
+            lock: lock.into(),
+        };
+        self.chain_spec.genesis.issued_cells.push(issued_cell);
+        self
+    }
+
+    pub fn with_genesis_epoch_length(mut self, length: u64) -> Self {
+        self.chain_spec.params.genesis_epoch_length = length;
+        self
+    }
+
+    pub fn with_epoch_duration_target(mut self, seconds: u64) -> Self {
+        self.chain_spec.params.epoch_duration_target = seconds;
+        self
+    }
+
+    /// Appends a system cell after the bundled system cells.
+    ///
+    /// The path is written into the chain spec as it is, so an absolute path is suggested.
+    pub fn with_system_cell<P: AsRef<Path>>(mut self, path: P, create_type_id: bool) -> Self {
+        let system_cell = spec::SystemCell {
+            create_type_id,
+            capacity: None,
+            file: res::Resource::file_system(path.as_ref().to_path_buf()),
+        };
+        self.chain_spec.genesis.system_cells.push(system_cell);
+        self
+    }
+
+    /// Builds the genesis block to compute all hashes, then renders the chain spec.
+    pub fn build(self) -> Result<DevChain> {
+        let mut chain_spec = self.chain_spec;
+        chain_spec.genesis.hash = None;
+        let consensus = derivation::build_consensus(&chain_spec).map_err(Error::ChainSpec)?;
+        let raw = derivation::load_hashes_from_consensus(&chain_spec, &consensus)
+            .map_err(Error::ChainSpec)?;
+        let spec = toml::Value::try_from(&chain_spec)
+            .and_then(|value| toml::to_string(&value))
+            .map_err(|err| Error::ChainSpec(err.to_string()))?;
+        let RawSpecHashes {
+            genesis,
+            cellbase,
+            system_cells,
+            dep_groups,
+        } = raw;
+        let (system_cells, extra_cells): (Vec<_>, Vec<_>) = system_cells
+            .into_iter()
+            .partition(|cell| Bundled::from_str(&cell.path).is_ok());
+        let extra_cells = extra_cells
+            .into_iter()
+            .map(|cell| (cell.path.clone(), cell.into()))
+            .collect();
+        let hashes = SpecHashes::try_from(RawSpecHashes {
+            genesis,
+            cellbase,
+            system_cells,
+            dep_groups,
+        })?;
+        Ok(DevChain {
+            spec,
+            hashes,
+            extra_cells,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ckb_chain_spec as spec;
+    use ckb_types::{bytes::Bytes, core::Capacity, packed, prelude::*};
+
+    use super::DevChainBuilder;
+    use crate::{
+        blockchain::{Bundled, DepGroupId, Network},
+        system_deps::SystemDeps,
+    };
+
+    #[test]
+    fn build_bundled_dev_chain() {
+        let chain = DevChainBuilder::new().unwrap().build().unwrap();
+        let hashes = chain.hashes();
+        let expected = SystemDeps::read().lookup_spec(Network::Develop).unwrap();
+        assert_eq!(hashes.genesis(), expected.genesis());
+        assert_eq!(hashes.cellbase(), expected.cellbase());
+        assert_eq!(hashes.system_cells().len(), expected.system_cells().len());
+        for bundled in Bundled::all() {
+            let cell = &hashes.system_cells()[bundled];
+            let expected_cell = &expected.system_cells()[bundled];
+            assert_eq!(cell.cell_dep(), expected_cell.cell_dep());
+            assert_eq!(cell.data_hash(), expected_cell.data_hash());
+            assert_eq!(cell.type_hash(), expected_cell.type_hash());
+        }
+        assert_eq!(hashes.dep_groups().len(), expected.dep_groups().len());
+        for dep_group_id in DepGroupId::all() {
+            let dep_group = &hashes.dep_groups()[dep_group_id];
+            let expected_dep_group = &expected.dep_groups()[dep_group_id];
+            assert_eq!(dep_group.cell_dep(), expected_dep_group.cell_dep());
+            assert_eq!(dep_group.members(), expected_dep_group.members());
+        }
+        assert!(chain.extra_cells().is_empty());
+    }
+
+    #[test]
+    fn issued_cell_changes_genesis() {
+        let lock = packed::Script::new_builder()
+            .args(Bytes::from(vec![0x11; 20]).pack())
+            .build();
+        let chain = DevChainBuilder::new()
+            .unwrap()
+            .with_issued_cell(lock, Capacity::shannons(100_000_000_000))
+            .build()
+            .unwrap();
+        let expected = SystemDeps::read().lookup_spec(Network::Develop).unwrap();
+        assert_ne!(chain.hashes().genesis(), expected.genesis());
+        assert_ne!(chain.hashes().cellbase(), expected.cellbase());
+    }
+
+    #[test]
+    fn rendered_spec_is_parsable() {
+        let chain = DevChainBuilder::new()
+            .unwrap()
+            .with_name("ckb_custom_dev")
+            .with_genesis_epoch_length(100)
+            .build()
+            .unwrap();
+        let chain_spec: spec::ChainSpec = toml::from_str(chain.spec()).unwrap();
+        assert_eq!(chain_spec.name, "ckb_custom_dev");
+        assert_eq!(chain_spec.params.genesis_epoch_length, 100);
+        assert!(chain_spec.genesis.hash.is_none());
+    }
+}
@@ -0,0 +1,199 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::time::Duration;
+
+use property::Property;
+use serde_json::{json, Value};
+
+use ckb_types::{core, H256};
+
+use crate::{
+    blockchain::{Bundled, Network},
+    error::{Error, Result},
+    genesis_check::Mismatch,
+    http,
+    system_deps::{SpecHashes, SystemDeps},
+};
+
+/// Compares the chain specification of a running node with the bundled one.
+#[derive(Property, Debug, Clone)]
+#[property(get(public), set(disable), mut(disable))]
+pub struct DriftChecker {
+    url: String,
+    timeout: Duration,
+}
+
+fn compare_consensus(spec: &SpecHashes, consensus: &Value) -> Result<Vec<Mismatch>> {
+    let mut mismatches = Vec::new();
+    let genesis_hash: H256 = serde_json::from_value(consensus["genesis_hash"].clone())?;
+    if &genesis_hash != spec.genesis() {
+        let item = "genesis hash in consensus".to_owned();
+        mismatches.push(Mismatch::new(item, spec.genesis(), &genesis_hash));
+    }
+    let type_hashes = [
+        ("dao_type_hash", Bundled::Dao),
+        (
+            "secp256k1_blake160_sighash_all_type_hash",
+            Bundled::Secp256k1Blake160,
+        ),
+        (
+            "secp256k1_blake160_multisig_all_type_hash",
+            Bundled::Secp256k1Blake160MultiSig,
+        ),
+    ];
+    for (field, bundled) in type_hashes.iter() {
+        let expected = spec
+            .system_cells()
+            .get(bundled)
+            .and_then(|cell| cell.type_hash().as_ref());
+        let actual: Option<H256> = serde_json::from_value(consensus[*field].clone())?;
+        match (expected, actual) {
+            (Some(expected), Some(actual)) if expected != &actual => {
+                let item = format!("{} in consensus", field);
+                mismatches.push(Mismatch::new(item, expected, &actual));
+            }
+            (Some(expected), None) => {
+                let item = format!("{} in consensus", field);
+                mismatches.push(Mismatch::missing(item, expected));
+            }
+            _ => {}
+        }
+    }
+    Ok(mismatches)
+}
+
+impl DriftChecker {
+    /// Creates a checker for the JSON-RPC endpoint of a node, such as `http://127.0.0.1:8114`.
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_owned(),
+            timeout: Duration::from_secs(30),
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": method,
+            "params": params,
+        });
+        let body = serde_json::to_vec(&request)?;
+        let response = http::post_json(&self.url, &body, self.timeout)?;
+        let mut response: Value = serde_json::from_slice(&response)?;
+        if let Some(error) = response.get("error") {
+            return Err(Error::Rpc(format!("{} failed: {}", method, error)));
+        }
+        match response.get_mut("result").map(Value::take) {
+            Some(Value::Null) | None => Err(Error::Rpc(format!("{} returned nothing", method))),
+            Some(result) => Ok(result),
+        }
+    }
+
+    /// Fetches the genesis block.
+    pub fn fetch_genesis(&self) -> Result<core::BlockView> {
+        let result = self.call("get_block_by_number", json!(["0x0"]))?;
+        let block: ckb_jsonrpc_types::BlockView = serde_json::from_value(result)?;
+        Ok(block.into())
+    }
+
+    /// Reports all differences between the node and the bundled chain specification of the
+    /// expected network, an empty list means no drift.
+    pub fn check(&self, network: Network) -> Result<Vec<Mismatch>> {
+        let spec = SystemDeps::read().lookup_spec(network)?;
+        let genesis = self.fetch_genesis()?;
+        let consensus = self.call("get_consensus", json!([]))?;
+        let mut mismatches = spec.genesis_mismatches(&genesis);
+        mismatches.extend(compare_consensus(spec, &consensus)?);
+        Ok(mismatches)
+    }
+}
+
+#[cfg(all(test, feature = "test-support"))]
+mod tests {
+    use std::{collections::HashMap, time::Duration};
+
+    use serde_json::json;
+
+    use super::DriftChecker;
+    use crate::{blockchain::Network, genesis::Genesis, mock_rpc::MockRpcServer};
+
+    fn checker(server: &MockRpcServer) -> DriftChecker {
+        DriftChecker::new(&server.url()).with_timeout(Duration::from_secs(5))
+    }
+
+    #[test]
+    fn no_drift_against_the_same_network() {
+        for network in Network::all() {
+            let server = MockRpcServer::start(*network).unwrap();
+            let mismatches = checker(&server).check(*network).unwrap();
+            assert!(mismatches.is_empty(), "{}: {:?}", network, mismatches);
+        }
+    }
+
+    #[test]
+    fn drift_against_another_network() {
+        let server = MockRpcServer::start(Network::Testnet).unwrap();
+        let mismatches = checker(&server).check(Network::Mainnet).unwrap();
+        assert!(mismatches.iter().any(|m| m.item() == "genesis hash"));
+        assert!(mismatches
+            .iter()
+            .any(|m| m.item() == "genesis hash in consensus"));
+    }
+
+    #[test]
+    fn drift_in_overridden_consensus() {
+        let mut overrides = HashMap::new();
+        overrides.insert(
+            "get_consensus".to_owned(),
+            json!({
+                "genesis_hash": format!("0x{}", "00".repeat(32)),
+                "dao_type_hash": null,
+                "secp256k1_blake160_sighash_all_type_hash": null,
+                "secp256k1_blake160_multisig_all_type_hash": null,
+            }),
+        );
+        let server = MockRpcServer::start_with_overrides(Network::Mainnet, overrides).unwrap();
+        let mismatches = checker(&server).check(Network::Mainnet).unwrap();
+        let items = mismatches
+            .iter()
+            .map(|m| m.item().as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            items,
+            vec![
+                "genesis hash in consensus",
+                "dao_type_hash in consensus",
+                "secp256k1_blake160_sighash_all_type_hash in consensus",
+                "secp256k1_blake160_multisig_all_type_hash in consensus",
+            ]
+        );
+        assert_eq!(mismatches[1].actual(), "nothing");
+    }
+
+    #[test]
+    fn drift_in_overridden_genesis() {
+        let genesis = Genesis::read().lookup(Network::Staging).clone();
+        let block: ckb_jsonrpc_types::BlockView = genesis.into();
+        let mut overrides = HashMap::new();
+        overrides.insert("get_block_by_number".to_owned(), json!(block));
+        let server = MockRpcServer::start_with_overrides(Network::Mainnet, overrides).unwrap();
+        let mismatches = checker(&server).check(Network::Mainnet).unwrap();
+        assert!(mismatches.iter().any(|m| m.item() == "genesis hash"));
+        assert!(mismatches.iter().any(|m| m.item() == "cellbase hash"));
+        assert!(!mismatches
+            .iter()
+            .any(|m| m.item() == "genesis hash in consensus"));
+    }
+}
@@ -10,7 +10,7 @@ use std::io;
 
 use thiserror::Error;
 
-use crate::blockchain;
+use crate::{blockchain, genesis_check::Mismatch};
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -21,13 +21,63 @@ pub enum Error {
     IO(#[from] io::Error),
     #[error("toml error: {0}")]
     Toml(#[from] toml::de::Error),
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
 
+    #[error("invalid spec hashes: {0}")]
+    InvalidSpecHashes(String),
+    #[error("unsupported format version {0}")]
+    UnsupportedVersion(u32),
     #[error("unknown {0} '{1}'")]
     Unknown(&'static str, String),
     #[error("unknown system cell: {0:?}, {1:?}")]
     UnknownSystemCell(blockchain::Network, blockchain::Bundled),
     #[error("unknown dep group: {0:?}, {1:?}")]
     UnknownDepGroup(blockchain::Network, blockchain::DepGroupId),
+
+    #[error("invalid block assembler: {0:?} is not a lock")]
+    InvalidBlockAssembler(blockchain::Bundled),
+    #[error("invalid config '{0}': {1}")]
+    InvalidConfig(String, &'static str),
+    #[error("invalid bootnode '{0}': {1}")]
+    InvalidBootNode(String, &'static str),
+
+    #[error("invalid DAO headers: {0}")]
+    InvalidDaoHeaders(String),
+    #[error("capacity error: {0}")]
+    Capacity(String),
+    #[error("invalid since {0:#x}: {1}")]
+    InvalidSince(u64, &'static str),
+
+    #[error("invalid script group: {0}")]
+    InvalidScriptGroup(String),
+    #[error("invalid witness at {0}: {1}")]
+    InvalidWitness(usize, String),
+    #[error("signing error: {0}")]
+    Signing(String),
+
+    #[error("unresolvable {0}")]
+    Unresolvable(String),
+    #[error("verification failed: {0}")]
+    Verification(String),
+    #[error("invalid block: {0}")]
+    InvalidBlock(String),
+
+    #[error("rpc error: {0}")]
+    Rpc(String),
+
+    #[error("chain spec error: {0}")]
+    ChainSpec(String),
+    #[error("genesis mismatched: {}", display_mismatches(.0))]
+    GenesisMismatch(Vec<Mismatch>),
+}
+
+fn display_mismatches(mismatches: &[Mismatch]) -> String {
+    mismatches
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
 }
 
 pub type Result<T> = ::std::result::Result<T, Error>;
@@ -0,0 +1,71 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::HashMap;
+
+use ckb_types::{bytes::Bytes, core, packed, prelude::*};
+
+use crate::{
+    blockchain::Network,
+    error::{Error, Result},
+};
+
+lazy_static::lazy_static! {
+    static ref GENESIS: Genesis = Genesis::initialize().unwrap();
+}
+
+/// The genesis blocks of the bundled chain specifications.
+pub struct Genesis(HashMap<Network, core::BlockView>);
+
+fn load_genesis_content(network: Network) -> &'static [u8] {
+    match network {
+        Network::Mainnet => include_bytes!(concat!(env!("OUT_DIR"), "/mainnet/genesis.bin")),
+        Network::Testnet => include_bytes!(concat!(env!("OUT_DIR"), "/testnet/genesis.bin")),
+        Network::Staging => include_bytes!(concat!(env!("OUT_DIR"), "/staging/genesis.bin")),
+        Network::Develop => include_bytes!(concat!(env!("OUT_DIR"), "/develop/genesis.bin")),
+    }
+}
+
+impl Genesis {
+    pub fn read() -> &'static Self {
+        &GENESIS
+    }
+
+    fn initialize() -> Result<Self> {
+        Network::all()
+            .iter()
+            .map(|network| {
+                let block =
+                    packed::Block::from_slice(load_genesis_content(*network)).map_err(|err| {
+                        Error::Unreachable(format!("genesis of {}: {}", network, err))
+                    })?;
+                Ok((*network, block.into_view()))
+            })
+            .collect::<Result<HashMap<_, _>>>()
+            .map(Self)
+    }
+
+    pub fn lookup(&self, network: Network) -> &core::BlockView {
+        self.0.get(&network).unwrap()
+    }
+
+    /// Finds a cell which was created in the genesis block of a network.
+    pub fn cell(
+        &self,
+        network: Network,
+        out_point: &packed::OutPoint,
+    ) -> Option<(packed::CellOutput, Bytes)> {
+        let tx_hash = out_point.tx_hash();
+        let index: u32 = out_point.index().unpack();
+        self.lookup(network)
+            .transactions()
+            .into_iter()
+            .find(|tx| tx.hash() == tx_hash)
+            .and_then(|tx| tx.output_with_data(index as usize))
+    }
+}
@@ -0,0 +1,221 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+
+use property::Property;
+
+use ckb_types::{bytes::Bytes, core, packed, prelude::*, H256};
+
+use crate::{
+    error::{Error, Result},
+    system_deps::SpecHashes,
+};
+
+/// An item which is different between a genesis block and the spec hashes.
+#[derive(Property, Debug, Clone, PartialEq, Eq)]
+#[property(get(public), set(disable), mut(disable))]
+pub struct Mismatch {
+    item: String,
+    expected: String,
+    actual: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: expected {} but got {}",
+            self.item, self.expected, self.actual
+        )
+    }
+}
+
+impl Mismatch {
+    pub(crate) fn new<E: fmt::LowerHex, A: fmt::LowerHex>(
+        item: String,
+        expected: &E,
+        actual: &A,
+    ) -> Self {
+        Self {
+            item,
+            expected: format!("{:#x}", expected),
+            actual: format!("{:#x}", actual),
+        }
+    }
+
+    pub(crate) fn missing<E: fmt::LowerHex>(item: String, expected: &E) -> Self {
+        Self {
+            item,
+            expected: format!("{:#x}", expected),
+            actual: "nothing".to_owned(),
+        }
+    }
+}
+
+fn find_cell(
+    txs: &[core::TransactionView],
+    tx_hash: &H256,
+    index: usize,
+) -> Option<(packed::CellOutput, Bytes)> {
+    txs.iter()
+        .find(|tx| {
+            let hash: H256 = tx.hash().unpack();
+            &hash == tx_hash
+        })
+        .and_then(|tx| tx.output_with_data(index))
+}
+
+impl SpecHashes {
+    /// Lists all differences between a genesis block and the spec hashes.
+    pub(crate) fn genesis_mismatches(&self, block: &core::BlockView) -> Vec<Mismatch> {
+        let mut mismatches = Vec::new();
+        let genesis_hash: H256 = block.hash().unpack();
+        if &genesis_hash != self.genesis() {
+            let item = "genesis hash".to_owned();
+            mismatches.push(Mismatch::new(item, self.genesis(), &genesis_hash));
+        }
+        let txs = block.transactions();
+        if let Some(cellbase) = txs.first() {
+            let cellbase_hash: H256 = cellbase.hash().unpack();
+            if &cellbase_hash != self.cellbase() {
+                let item = "cellbase hash".to_owned();
+                mismatches.push(Mismatch::new(item, self.cellbase(), &cellbase_hash));
+            }
+        } else {
+            mismatches.push(Mismatch::missing("cellbase".to_owned(), self.cellbase()));
+        }
+        for (bundled, cell) in self.iter_system_cells() {
+            if let Some((output, data)) = find_cell(&txs, cell.tx_hash(), cell.index()) {
+                let data_hash: H256 = packed::CellOutput::calc_data_hash(&data).unpack();
+                if &data_hash != cell.data_hash() {
+                    let item = format!("data hash of {:?}", bundled);
+                    mismatches.push(Mismatch::new(item, cell.data_hash(), &data_hash));
+                }
+                let type_hash: Option<H256> = output
+                    .type_()
+                    .to_opt()
+                    .map(|script| script.calc_script_hash().unpack());
+                match (cell.type_hash(), type_hash) {
+                    (Some(expected), Some(actual)) if expected != &actual => {
+                        let item = format!("type hash of {:?}", bundled);
+                        mismatches.push(Mismatch::new(item, expected, &actual));
+                    }
+                    (Some(expected), None) => {
+                        let item = format!("type hash of {:?}", bundled);
+                        mismatches.push(Mismatch::missing(item, expected));
+                    }
+                    (None, Some(actual)) => mismatches.push(Mismatch {
+                        item: format!("type hash of {:?}", bundled),
+                        expected: "nothing".to_owned(),
+                        actual: format!("{:#x}", actual),
+                    }),
+                    _ => {}
+                }
+            } else {
+                let item = format!("out point of {:?}, index {}", bundled, cell.index());
+                mismatches.push(Mismatch::missing(item, cell.tx_hash()));
+            }
+        }
+        for (dep_group_id, dep_group) in self.iter_dep_groups() {
+            if let Some((_, data)) = find_cell(&txs, dep_group.tx_hash(), dep_group.index()) {
+                let expected = dep_group.members().pack();
+                if data.as_ref() != expected.as_slice() {
+                    let actual = packed::CellOutput::calc_data_hash(&data);
+                    let expected = packed::CellOutput::calc_data_hash(expected.as_slice());
+                    let item = format!("data hash of dep group {:?}", dep_group_id);
+                    let expected: H256 = expected.unpack();
+                    let actual: H256 = actual.unpack();
+                    mismatches.push(Mismatch::new(item, &expected, &actual));
+                }
+            } else {
+                let item = format!(
+                    "out point of dep group {:?}, index {}",
+                    dep_group_id,
+                    dep_group.index()
+                );
+                mismatches.push(Mismatch::missing(item, dep_group.tx_hash()));
+            }
+        }
+        mismatches
+    }
+
+    /// Checks a genesis block, which may come from untrusted sources, against the spec hashes.
+    ///
+    /// All hashes are calculated from the block, the hashes in the block are not trusted.
+    pub fn verify_genesis(&self, block: &packed::Block) -> Result<()> {
+        let mismatches = self.genesis_mismatches(&block.clone().into_view());
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::GenesisMismatch(mismatches))
+        }
+    }
+}
+
+#[cfg(all(test, any(feature = "verifier", feature = "test-support")))]
+mod tests {
+    use ckb_types::{bytes::Bytes, prelude::*};
+
+    use crate::{
+        blockchain::{Bundled, Network},
+        error::Error,
+        genesis::Genesis,
+        system_deps::SystemDeps,
+    };
+
+    #[test]
+    fn verify_bundled_genesis() {
+        for network in Network::all() {
+            let spec = SystemDeps::read().lookup_spec(*network).unwrap();
+            let block = Genesis::read().lookup(*network).data();
+            assert!(spec.verify_genesis(&block).is_ok(), "{}", network);
+        }
+    }
+
+    #[test]
+    fn verify_genesis_of_other_network() {
+        let spec = SystemDeps::read().lookup_spec(Network::Mainnet).unwrap();
+        let block = Genesis::read().lookup(Network::Testnet).data();
+        let mismatches = match spec.verify_genesis(&block) {
+            Err(Error::GenesisMismatch(mismatches)) => mismatches,
+            result => panic!("unexpected result {:?}", result),
+        };
+        assert!(mismatches.iter().any(|m| m.item() == "genesis hash"));
+        assert!(mismatches.iter().any(|m| m.item() == "cellbase hash"));
+    }
+
+    #[test]
+    fn verify_genesis_with_altered_data() {
+        let spec = SystemDeps::read().lookup_spec(Network::Develop).unwrap();
+        let genesis = Genesis::read().lookup(Network::Develop);
+        let index = spec.system_cells()[&Bundled::Dao].index();
+        let mut txs = genesis.transactions();
+        let cellbase = txs[0].clone();
+        let mut outputs_data = cellbase.outputs_data().into_iter().collect::<Vec<_>>();
+        let mut data = outputs_data[index].raw_data().to_vec();
+        data[0] ^= 0x01;
+        outputs_data[index] = Bytes::from(data).pack();
+        // Keep the hashes, so only the data is different.
+        txs[0] = cellbase
+            .as_advanced_builder()
+            .set_outputs_data(outputs_data)
+            .build()
+            .fake_hash(cellbase.hash());
+        let block = genesis
+            .as_advanced_builder()
+            .set_transactions(txs)
+            .build_unchecked();
+        let items = spec
+            .genesis_mismatches(&block)
+            .into_iter()
+            .map(|m| m.item().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(items, vec![format!("data hash of {:?}", Bundled::Dao)]);
+    }
+}
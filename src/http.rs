@@ -0,0 +1,132 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A minimal HTTP/1.1 layer for JSON-RPC, only `Content-Length` bodies are supported.
+
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+/// The largest expected body is a genesis block in JSON, which is only a few MiB.
+const MAX_CONTENT_LENGTH: usize = 16 * 1024 * 1024;
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Reads a request or a response, returns the start line and the body.
+pub(crate) fn read_message<R: Read>(reader: R) -> io::Result<(String, Vec<u8>)> {
+    let mut reader = BufReader::new(reader);
+    let mut start_line = String::new();
+    reader.read_line(&mut start_line)?;
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(invalid_data("unexpected end of headers".to_owned()));
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let mut parts = line.splitn(2, ':');
+        let name = parts.next().unwrap_or_default().trim();
+        let value = parts.next().unwrap_or_default().trim();
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = value
+                .parse()
+                .map_err(|_| invalid_data(format!("invalid content length '{}'", value)))?;
+            if content_length > MAX_CONTENT_LENGTH {
+                return Err(invalid_data(format!(
+                    "content length {} exceeds the limit {}",
+                    content_length, MAX_CONTENT_LENGTH
+                )));
+            }
+        }
+    }
+    let mut body = vec![0u8; content_length];
+    reader.read_exact(&mut body)?;
+    Ok((start_line.trim_end().to_owned(), body))
+}
+
+pub(crate) fn write_response<W: Write>(mut writer: W, status: &str, body: &[u8]) -> io::Result<()> {
+    write!(
+        writer,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        body.len()
+    )?;
+    writer.write_all(body)?;
+    writer.flush()
+}
+
+/// Posts a JSON body to an url like `http://127.0.0.1:8114/`, returns the response body.
+///
+/// The timeout applies to connecting, and to each read and write; only the first resolved
+/// address is tried.
+pub(crate) fn post_json(url: &str, body: &[u8], timeout: Duration) -> io::Result<Vec<u8>> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| invalid_data(format!("unsupported url '{}'", url)))?;
+    let (authority, path) = if let Some(pos) = rest.find('/') {
+        (&rest[..pos], &rest[pos..])
+    } else {
+        (rest, "/")
+    };
+    let addr = authority
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| invalid_data(format!("no address for '{}'", authority)))?;
+    let mut stream = TcpStream::connect_timeout(&addr, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    write!(
+        stream,
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        path,
+        authority,
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    stream.flush()?;
+    let (status_line, body) = read_message(&mut stream)?;
+    if status_line.split_whitespace().nth(1) != Some("200") {
+        return Err(invalid_data(format!(
+            "unexpected response '{}'",
+            status_line
+        )));
+    }
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::{read_message, MAX_CONTENT_LENGTH};
+
+    #[test]
+    fn read_message_with_body() {
+        let message = b"POST / HTTP/1.1\r\nContent-Length: 4\r\n\r\nbody";
+        let (start_line, body) = read_message(&message[..]).unwrap();
+        assert_eq!(start_line, "POST / HTTP/1.1");
+        assert_eq!(body, b"body");
+    }
+
+    #[test]
+    fn reject_large_content_length() {
+        let message = format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n",
+            MAX_CONTENT_LENGTH + 1
+        );
+        let err = read_message(message.as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,181 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::convert::TryFrom;
+
+use ckb_types::{
+    core::{DepType, ScriptHashType},
+    packed,
+    prelude::*,
+    H256,
+};
+
+use crate::{
+    blockchain::{Bundled, DepGroupId, Network},
+    system_deps::SystemDeps,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SystemEntry {
+    Cell(Bundled),
+    DepGroup(DepGroupId),
+}
+
+impl SystemDeps {
+    /// Finds the system cells which a script runs, for all networks.
+    ///
+    /// The same system cell could have the same code hash in different networks, so all matched
+    /// networks are returned.
+    pub fn identify_script(&self, script: &packed::Script) -> Vec<(Network, Bundled)> {
+        let hash_type = if let Ok(hash_type) = ScriptHashType::try_from(script.hash_type()) {
+            hash_type
+        } else {
+            return Vec::new();
+        };
+        let code_hash: H256 = script.code_hash().unpack();
+        let mut matched = self
+            .specs()
+            .iter()
+            .flat_map(|(network, spec)| {
+                spec.system_cells()
+                    .iter()
+                    .filter(|(_, cell)| match hash_type {
+                        ScriptHashType::Data => cell.data_hash() == &code_hash,
+                        ScriptHashType::Type => cell.type_hash().as_ref() == Some(&code_hash),
+                    })
+                    .map(move |(bundled, _)| (*network, *bundled))
+            })
+            .collect::<Vec<_>>();
+        matched.sort();
+        matched
+    }
+
+    /// Finds the system cell or the dep group which an out point points to.
+    pub fn identify_out_point(
+        &self,
+        out_point: &packed::OutPoint,
+    ) -> Option<(Network, SystemEntry)> {
+        let tx_hash: H256 = out_point.tx_hash().unpack();
+        let index: u32 = out_point.index().unpack();
+        let index = index as usize;
+        self.specs().iter().find_map(|(network, spec)| {
+            spec.system_cells()
+                .iter()
+                .find(|(_, cell)| cell.tx_hash() == &tx_hash && cell.index() == index)
+                .map(|(bundled, _)| SystemEntry::Cell(*bundled))
+                .or_else(|| {
+                    spec.dep_groups()
+                        .iter()
+                        .find(|(_, dep_group)| {
+                            dep_group.tx_hash() == &tx_hash && dep_group.index() == index
+                        })
+                        .map(|(dep_group_id, _)| SystemEntry::DepGroup(*dep_group_id))
+                })
+                .map(|entry| (*network, entry))
+        })
+    }
+
+    /// Finds the system cell or the dep group which a cell dep refers to.
+    ///
+    /// The dep type should be consistent with the kind of the found entry.
+    pub fn identify_cell_dep(&self, cell_dep: &packed::CellDep) -> Option<(Network, SystemEntry)> {
+        let dep_type = DepType::try_from(cell_dep.dep_type()).ok()?;
+        self.identify_out_point(&cell_dep.out_point())
+            .filter(|(_, entry)| {
+                matches!(
+                    (dep_type, entry),
+                    (DepType::Code, SystemEntry::Cell(_))
+                        | (DepType::DepGroup, SystemEntry::DepGroup(_))
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ckb_types::{
+        core::{DepType, ScriptHashType},
+        packed,
+        prelude::*,
+        H256,
+    };
+
+    use super::SystemEntry;
+    use crate::{
+        blockchain::{Bundled, DepGroupId, Network},
+        system_deps::SystemDeps,
+    };
+
+    fn type_script(code_hash: &H256) -> packed::Script {
+        packed::Script::new_builder()
+            .code_hash(code_hash.pack())
+            .hash_type(ScriptHashType::Type.into())
+            .build()
+    }
+
+    #[test]
+    fn identify_scripts() {
+        let system_deps = SystemDeps::read();
+        let cell = system_deps
+            .lookup_system_cell(Network::Mainnet, Bundled::Dao)
+            .unwrap();
+        let script = type_script(cell.type_hash().as_ref().unwrap());
+        let matched = system_deps.identify_script(&script);
+        // The type ids of bundled system cells are the same in all networks.
+        assert!(matched.contains(&(Network::Mainnet, Bundled::Dao)));
+        assert!(matched.contains(&(Network::Testnet, Bundled::Dao)));
+        assert!(matched.iter().all(|(_, bundled)| *bundled == Bundled::Dao));
+        let data_script = packed::Script::new_builder()
+            .code_hash(cell.data_hash().pack())
+            .hash_type(ScriptHashType::Data.into())
+            .build();
+        let matched = system_deps.identify_script(&data_script);
+        assert!(matched.contains(&(Network::Mainnet, Bundled::Dao)));
+        assert!(system_deps
+            .identify_script(&type_script(&H256::default()))
+            .is_empty());
+    }
+
+    #[test]
+    fn identify_out_points_and_cell_deps() {
+        let system_deps = SystemDeps::read();
+        for network in Network::all() {
+            for bundled in Bundled::all() {
+                let cell = system_deps.lookup_system_cell(*network, *bundled).unwrap();
+                let expected = Some((*network, SystemEntry::Cell(*bundled)));
+                assert_eq!(system_deps.identify_out_point(&cell.out_point()), expected);
+                assert_eq!(system_deps.identify_cell_dep(&cell.cell_dep()), expected);
+            }
+            for dep_group_id in DepGroupId::all() {
+                let dep_group = system_deps
+                    .lookup_dep_group(*network, *dep_group_id)
+                    .unwrap();
+                let expected = Some((*network, SystemEntry::DepGroup(*dep_group_id)));
+                assert_eq!(
+                    system_deps.identify_out_point(&dep_group.out_point()),
+                    expected
+                );
+                assert_eq!(
+                    system_deps.identify_cell_dep(&dep_group.cell_dep()),
+                    expected
+                );
+                // The dep type is inconsistent with the entry.
+                let cell_dep = dep_group
+                    .cell_dep()
+                    .as_builder()
+                    .dep_type(DepType::Code.into())
+                    .build();
+                assert_eq!(system_deps.identify_cell_dep(&cell_dep), None);
+            }
+        }
+        assert_eq!(
+            system_deps.identify_out_point(&packed::OutPoint::default()),
+            None
+        );
+    }
+}
@@ -0,0 +1,177 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::HashMap;
+
+use property::Property;
+use serde_derive::Deserialize;
+
+use ckb_types::{bytes::Bytes, core::Capacity, packed, prelude::*, H256};
+
+use crate::{blockchain::Network, error::Result};
+
+lazy_static::lazy_static! {
+    static ref ISSUANCES: Issuances = Issuances::initialize().unwrap();
+}
+
+#[derive(Deserialize)]
+struct RawIssuedCell {
+    index: u32,
+    capacity: u64,
+    code_hash: H256,
+    hash_type: u8,
+    args: Vec<u8>,
+}
+
+#[derive(Deserialize)]
+struct RawIssuance {
+    cellbase_hash: H256,
+    total: u64,
+    system_cells_occupied: u64,
+    burned: u64,
+    issued_cells: Vec<RawIssuedCell>,
+}
+
+/// The issuance in the genesis blocks of the bundled chain specifications.
+pub struct Issuances(HashMap<Network, Issuance>);
+
+#[derive(Property, Debug, Clone)]
+#[property(get(public), set(disable), mut(disable))]
+pub struct Issuance {
+    /// The cells which were issued by the chain specification, in the genesis cellbase.
+    issued_cells: Vec<IssuedCell>,
+    /// The total capacity of all cells in the genesis block.
+    total: Capacity,
+    /// The occupied capacity of all system cells.
+    system_cells_occupied: Capacity,
+    /// The total capacity of the issued cells which are locked by the zero code hash, they could
+    /// never be unlocked.
+    ///
+    /// The system cells and the dep groups are locked by the zero code hash too, but they are
+    /// not counted.
+    burned: Capacity,
+}
+
+#[derive(Property, Debug, Clone)]
+#[property(get(public), set(disable), mut(disable))]
+pub struct IssuedCell {
+    lock: packed::Script,
+    capacity: Capacity,
+    out_point: packed::OutPoint,
+}
+
+impl Issuances {
+    pub fn read() -> &'static Self {
+        &ISSUANCES
+    }
+
+    fn initialize() -> Result<Self> {
+        let raw_issuances: HashMap<String, RawIssuance> =
+            toml::from_str(include_str!(concat!(env!("OUT_DIR"), "/issuance.toml")))?;
+        raw_issuances
+            .into_iter()
+            .map(|(spec_name, raw)| {
+                let network = Network::from_spec_name(&spec_name)?;
+                let cellbase_hash = raw.cellbase_hash.pack();
+                let issued_cells = raw
+                    .issued_cells
+                    .into_iter()
+                    .map(|cell| {
+                        let lock = packed::Script::new_builder()
+                            .code_hash(cell.code_hash.pack())
+                            .hash_type(packed::Byte::new(cell.hash_type))
+                            .args(Bytes::from(cell.args).pack())
+                            .build();
+                        IssuedCell {
+                            lock,
+                            capacity: Capacity::shannons(cell.capacity),
+                            out_point: packed::OutPoint::new(cellbase_hash.clone(), cell.index),
+                        }
+                    })
+                    .collect();
+                let issuance = Issuance {
+                    issued_cells,
+                    total: Capacity::shannons(raw.total),
+                    system_cells_occupied: Capacity::shannons(raw.system_cells_occupied),
+                    burned: Capacity::shannons(raw.burned),
+                };
+                Ok((network, issuance))
+            })
+            .collect::<Result<HashMap<_, _>>>()
+            .map(Self)
+    }
+
+    pub fn lookup(&self, network: Network) -> &Issuance {
+        self.0.get(&network).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ckb_types::core::Capacity;
+
+    use super::Issuances;
+    use crate::{blockchain::Network, consensus::ConsensusParams};
+    #[cfg(any(feature = "verifier", feature = "test-support"))]
+    use crate::{dao::DaoField, genesis::Genesis};
+
+    /// The rewards of the genesis block, which are counted in the DAO field of the genesis.
+    fn genesis_rewards(network: Network) -> Capacity {
+        let consensus = ConsensusParams::read().lookup(network);
+        let length = consensus.genesis_epoch_length();
+        let block_reward = |epoch_reward: &Capacity| {
+            let epoch_reward = epoch_reward.as_u64();
+            // The remainder is shared by the first blocks in the epoch.
+            epoch_reward / length + if epoch_reward % length > 0 { 1 } else { 0 }
+        };
+        let primary = block_reward(consensus.initial_primary_epoch_reward());
+        let secondary = block_reward(consensus.secondary_epoch_reward());
+        Capacity::shannons(primary + secondary)
+    }
+
+    #[test]
+    fn mainnet_total() {
+        let issuance = Issuances::read().lookup(Network::Mainnet);
+        let total = issuance
+            .total()
+            .safe_add(genesis_rewards(Network::Mainnet))
+            .unwrap();
+        // The `C` in the DAO field of the mainnet genesis block.
+        assert_eq!(total, Capacity::shannons(3_360_000_145_238_488_200));
+    }
+
+    #[test]
+    fn burned_and_occupied() {
+        for network in Network::all() {
+            let issuance = Issuances::read().lookup(*network);
+            let issued = issuance
+                .issued_cells()
+                .iter()
+                .map(|cell| cell.capacity().as_u64())
+                .sum::<u64>();
+            assert!(issuance.burned().as_u64() <= issued);
+            let counted = issued + issuance.system_cells_occupied().as_u64();
+            assert!(counted <= issuance.total().as_u64());
+        }
+    }
+
+    #[cfg(any(feature = "verifier", feature = "test-support"))]
+    #[test]
+    fn total_in_genesis_dao() {
+        for network in Network::all() {
+            let header = Genesis::read().lookup(*network).header();
+            let dao = DaoField::from_header(&header);
+            let total = Issuances::read()
+                .lookup(*network)
+                .total()
+                .safe_add(genesis_rewards(*network))
+                .unwrap();
+            assert_eq!(&total, dao.c(), "{}", network);
+        }
+    }
+}
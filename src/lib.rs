@@ -10,13 +10,73 @@ pub mod error;
 
 pub mod blockchain;
 pub mod constants;
+pub mod dao;
+pub mod signing;
 
 mod bootnodes;
+mod config;
+mod consensus;
+mod genesis_check;
+mod identify;
+mod issuance;
+mod lint;
+mod node_config;
+mod planner;
+mod raw;
+mod resolve;
 mod serialized;
+mod since;
 mod system_deps;
 
+#[cfg(feature = "dev-chain")]
+mod derivation;
+#[cfg(feature = "test-support")]
+mod dev_accounts;
+#[cfg(feature = "dev-chain")]
+mod dev_chain;
+#[cfg(feature = "rpc")]
+mod drift;
+#[cfg(any(feature = "verifier", feature = "test-support"))]
+mod genesis;
+#[cfg(feature = "rpc")]
+mod http;
+#[cfg(feature = "test-support")]
+mod mock_chain;
+#[cfg(feature = "test-support")]
+mod mock_rpc;
+#[cfg(feature = "probe")]
+mod probe;
+#[cfg(feature = "verifier")]
+mod verifier;
+
 pub use crate::{
-    bootnodes::BootNodes,
+    bootnodes::{BootNode, BootNodeFilter, BootNodes, Host, Transport},
+    config::{BlockAssembler, ConfigRenderer},
+    consensus::{Consensus, ConsensusParams},
+    genesis_check::Mismatch,
+    identify::SystemEntry,
+    issuance::{Issuance, Issuances, IssuedCell},
+    lint::{Diagnostic, Location},
+    node_config::{NetworkConfig, NodeConfig, NodeConfigs, RpcConfig, StoreConfig, TxPoolConfig},
+    resolve::CellProvider,
     serialized::{BaseSerializedSize, BaseStruct},
+    since::Since,
     system_deps::{DepGroup, SpecHashes, SystemCell, SystemDeps},
 };
+
+#[cfg(feature = "dev-chain")]
+pub use crate::dev_chain::{DevChain, DevChainBuilder};
+#[cfg(feature = "rpc")]
+pub use crate::drift::DriftChecker;
+#[cfg(any(feature = "verifier", feature = "test-support"))]
+pub use crate::genesis::Genesis;
+#[cfg(feature = "probe")]
+pub use crate::probe::{ProbeOptions, ProbeReport, ProbeStatus};
+#[cfg(feature = "verifier")]
+pub use crate::verifier::OfflineVerifier;
+#[cfg(feature = "test-support")]
+pub use crate::{
+    dev_accounts::{DevAccount, DevAccounts},
+    mock_chain::MockChain,
+    mock_rpc::MockRpcServer,
+};
@@ -0,0 +1,173 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+
+use property::Property;
+
+use ckb_types::{packed, prelude::*};
+
+use crate::{blockchain::Network, identify::SystemEntry, system_deps::SystemDeps};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Location {
+    CellDep(usize),
+    OutputLock(usize),
+    OutputType(usize),
+}
+
+/// A system entry which is used in a transaction but belongs to other networks.
+#[derive(Property, Debug, Clone)]
+#[property(get(public), set(disable), mut(disable))]
+pub struct Diagnostic {
+    location: Location,
+    entry: SystemEntry,
+    networks: Vec<Network>,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::CellDep(index) => write!(f, "cell_deps[{}]", index),
+            Self::OutputLock(index) => write!(f, "outputs[{}].lock", index),
+            Self::OutputType(index) => write!(f, "outputs[{}].type", index),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} refers to {:?} which belongs to {:?}",
+            self.location, self.entry, self.networks
+        )
+    }
+}
+
+impl SystemDeps {
+    /// Reports a script which only runs the system cells of other networks.
+    ///
+    /// The bundled system cells have the same data hashes and the same type ids in all bundled
+    /// networks, since the type ids are derived from the same cellbase input, so the scripts of
+    /// bundled system cells are never reported, only the spec hashes which are loaded from other
+    /// sources could differ.
+    fn lint_script(
+        &self,
+        script: &packed::Script,
+        network: Network,
+        location: Location,
+    ) -> Option<Diagnostic> {
+        let matched = self.identify_script(script);
+        if matched.is_empty() || matched.iter().any(|(n, _)| *n == network) {
+            None
+        } else {
+            let entry = SystemEntry::Cell(matched[0].1);
+            let networks = matched.into_iter().map(|(n, _)| n).collect();
+            Some(Diagnostic {
+                location,
+                entry,
+                networks,
+            })
+        }
+    }
+
+    /// Reports all cell deps, lock scripts and type scripts in a transaction which refer to the
+    /// system entries of other networks but not the target network.
+    ///
+    /// For the bundled networks, only the out points of cell deps could be reported.
+    pub fn lint_transaction(&self, tx: &packed::Transaction, network: Network) -> Vec<Diagnostic> {
+        let raw = tx.raw();
+        let mut diagnostics = Vec::new();
+        for (index, cell_dep) in raw.cell_deps().into_iter().enumerate() {
+            if let Some((n, entry)) = self.identify_out_point(&cell_dep.out_point()) {
+                if n != network {
+                    diagnostics.push(Diagnostic {
+                        location: Location::CellDep(index),
+                        entry,
+                        networks: vec![n],
+                    });
+                }
+            }
+        }
+        for (index, output) in raw.outputs().into_iter().enumerate() {
+            let location = Location::OutputLock(index);
+            if let Some(diagnostic) = self.lint_script(&output.lock(), network, location) {
+                diagnostics.push(diagnostic);
+            }
+            if let Some(script) = output.type_().to_opt() {
+                let location = Location::OutputType(index);
+                if let Some(diagnostic) = self.lint_script(&script, network, location) {
+                    diagnostics.push(diagnostic);
+                }
+            }
+        }
+        diagnostics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ckb_types::{
+        core::{ScriptHashType, TransactionBuilder},
+        packed,
+        prelude::*,
+    };
+
+    use super::Location;
+    use crate::{
+        blockchain::{Bundled, DepGroupId, Network},
+        identify::SystemEntry,
+        system_deps::SystemDeps,
+    };
+
+    fn transaction(network: Network) -> packed::Transaction {
+        let system_deps = SystemDeps::read();
+        let dep_group = system_deps
+            .lookup_dep_group(network, DepGroupId::Secp256k1Blake160)
+            .unwrap();
+        let cell = system_deps
+            .lookup_system_cell(network, Bundled::Secp256k1Blake160)
+            .unwrap();
+        let lock = packed::Script::new_builder()
+            .code_hash(cell.type_hash().clone().unwrap().pack())
+            .hash_type(ScriptHashType::Type.into())
+            .build();
+        let output = packed::CellOutput::new_builder().lock(lock).build();
+        TransactionBuilder::default()
+            .cell_dep(dep_group.cell_dep())
+            .output(output)
+            .output_data(Default::default())
+            .build()
+            .data()
+    }
+
+    #[test]
+    fn lint_cell_dep_of_other_network() {
+        let tx = transaction(Network::Mainnet);
+        let diagnostics = SystemDeps::read().lint_transaction(&tx, Network::Testnet);
+        assert_eq!(diagnostics.len(), 1);
+        let diagnostic = &diagnostics[0];
+        assert_eq!(diagnostic.location(), &Location::CellDep(0));
+        assert_eq!(
+            diagnostic.entry(),
+            &SystemEntry::DepGroup(DepGroupId::Secp256k1Blake160)
+        );
+        assert_eq!(diagnostic.networks(), &vec![Network::Mainnet]);
+    }
+
+    #[test]
+    fn lint_same_network() {
+        for network in Network::all() {
+            let tx = transaction(*network);
+            assert!(SystemDeps::read()
+                .lint_transaction(&tx, *network)
+                .is_empty());
+        }
+    }
+}
@@ -0,0 +1,350 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::{HashMap, HashSet};
+
+use ckb_types::{
+    bytes::Bytes,
+    core::{cell::ResolvedTransaction, BlockView, EpochNumberWithFraction, HeaderView},
+    packed,
+    prelude::*,
+};
+
+use crate::{
+    blockchain::Network,
+    consensus::ConsensusParams,
+    error::{Error, Result},
+    genesis::Genesis,
+    resolve::{resolve_transaction, CellProvider},
+};
+
+#[derive(Debug, Clone)]
+struct MockCell {
+    output: packed::CellOutput,
+    data: Bytes,
+    block_hash: packed::Byte32,
+}
+
+/// An in-memory chain for tests, which starts from the bundled genesis block of a network.
+///
+/// The blocks are not verified, only the links between blocks and the liveness of the inputs
+/// are checked.
+#[derive(Debug, Clone)]
+pub struct MockChain {
+    network: Network,
+    tip: HeaderView,
+    headers: HashMap<packed::Byte32, HeaderView>,
+    cells: HashMap<packed::OutPoint, MockCell>,
+    spent: HashSet<packed::OutPoint>,
+    mocked_count: u64,
+}
+
+impl MockChain {
+    /// Creates a chain which only has the genesis block of a network.
+    pub fn new(network: Network) -> Self {
+        let genesis = Genesis::read().lookup(network);
+        let mut chain = Self {
+            network,
+            tip: genesis.header(),
+            headers: HashMap::new(),
+            cells: HashMap::new(),
+            spent: HashSet::new(),
+            mocked_count: 0,
+        };
+        chain.attach_block(genesis);
+        chain
+    }
+
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    pub fn tip(&self) -> &HeaderView {
+        &self.tip
+    }
+
+    pub fn get_header(&self, block_hash: &packed::Byte32) -> Option<&HeaderView> {
+        self.headers.get(block_hash)
+    }
+
+    /// Checks whether a cell exists and has not been spent.
+    pub fn is_live(&self, out_point: &packed::OutPoint) -> bool {
+        self.cells.contains_key(out_point) && !self.spent.contains(out_point)
+    }
+
+    pub fn is_spent(&self, out_point: &packed::OutPoint) -> bool {
+        self.spent.contains(out_point)
+    }
+
+    /// Inserts a live cell into the tip block, without any transaction.
+    ///
+    /// The transaction hash of the returned out point is unique in the chain.
+    pub fn add_cell(&mut self, output: packed::CellOutput, data: Bytes) -> packed::OutPoint {
+        self.mocked_count += 1;
+        let mut seed = b"mock-cell".to_vec();
+        seed.extend_from_slice(&self.mocked_count.to_le_bytes());
+        let tx_hash = ckb_hash::blake2b_256(&seed).pack();
+        let out_point = packed::OutPoint::new(tx_hash, 0);
+        let cell = MockCell {
+            output,
+            data,
+            block_hash: self.tip.hash(),
+        };
+        self.cells.insert(out_point.clone(), cell);
+        out_point
+    }
+
+    /// Appends a block to the tip, the inputs of transactions are marked as spent.
+    ///
+    /// The block should be the child of the tip, and all inputs should be live.
+    pub fn add_block(&mut self, block: &BlockView) -> Result<()> {
+        if block.parent_hash() != self.tip.hash() || block.number() != self.tip.number() + 1 {
+            return Err(Error::InvalidBlock(format!(
+                "block {} is not a child of the tip {}",
+                block.hash(),
+                self.tip.hash()
+            )));
+        }
+        let mut spending = HashSet::new();
+        for tx in block.transactions().iter().filter(|tx| !tx.is_cellbase()) {
+            for out_point in tx.input_pts_iter() {
+                if !self.is_live(&out_point) || !spending.insert(out_point.clone()) {
+                    return Err(Error::InvalidBlock(format!(
+                        "input {} of transaction {} is not live",
+                        out_point,
+                        tx.hash()
+                    )));
+                }
+            }
+        }
+        self.spent.extend(spending);
+        self.attach_block(block);
+        Ok(())
+    }
+
+    fn attach_block(&mut self, block: &BlockView) {
+        let block_hash = block.hash();
+        for tx in block.transactions() {
+            let tx_hash = tx.hash();
+            for (index, (output, data)) in tx.outputs_with_data_iter().enumerate() {
+                let out_point = packed::OutPoint::new(tx_hash.clone(), index as u32);
+                let cell = MockCell {
+                    output,
+                    data,
+                    block_hash: block_hash.clone(),
+                };
+                self.cells.insert(out_point, cell);
+            }
+        }
+        self.headers.insert(block_hash, block.header());
+        self.tip = block.header();
+    }
+
+    fn next_epoch(&self) -> EpochNumberWithFraction {
+        let epoch = self.tip.epoch();
+        if epoch.index() + 1 < epoch.length() {
+            EpochNumberWithFraction::new(epoch.number(), epoch.index() + 1, epoch.length())
+        } else {
+            let length = ConsensusParams::read()
+                .lookup(self.network)
+                .genesis_epoch_length();
+            EpochNumberWithFraction::new(epoch.number() + 1, 0, length)
+        }
+    }
+
+    fn build_block(
+        &self,
+        epoch: EpochNumberWithFraction,
+        txs: &[packed::Transaction],
+    ) -> BlockView {
+        let consensus = ConsensusParams::read().lookup(self.network);
+        let interval =
+            consensus.epoch_duration_target() * 1000 / consensus.genesis_epoch_length().max(1);
+        BlockView::new_advanced_builder()
+            .parent_hash(self.tip.hash())
+            .number((self.tip.number() + 1).pack())
+            .epoch(epoch.full_value().pack())
+            .timestamp((self.tip.timestamp() + interval).pack())
+            .compact_target(self.tip.compact_target().pack())
+            .dao(self.tip.dao())
+            .transactions(txs.iter().map(|tx| tx.clone().into_view()).collect())
+            .build()
+    }
+
+    /// Packs transactions into a new block on the tip.
+    ///
+    /// The DAO field is copied from the parent, and no cellbase is included.
+    pub fn mine(&mut self, txs: &[packed::Transaction]) -> Result<HeaderView> {
+        let block = self.build_block(self.next_epoch(), txs);
+        self.add_block(&block)?;
+        Ok(block.header())
+    }
+
+    /// Appends an empty block which is at the start of the epoch after some epochs.
+    ///
+    /// The count should not be zero, since the block would not be the start of an epoch.
+    pub fn advance_epochs(&mut self, count: u64) -> Result<HeaderView> {
+        if count == 0 {
+            return Err(Error::InvalidBlock(
+                "advance the chain by zero epochs".to_owned(),
+            ));
+        }
+        let epoch = self.tip.epoch();
+        let length = ConsensusParams::read()
+            .lookup(self.network)
+            .genesis_epoch_length();
+        let epoch = EpochNumberWithFraction::new(epoch.number() + count, 0, length);
+        let block = self.build_block(epoch, &[]);
+        self.add_block(&block)?;
+        Ok(block.header())
+    }
+
+    /// Resolves a transaction, all inputs and cell deps should be live.
+    pub fn resolve(&self, tx: &packed::Transaction) -> Result<ResolvedTransaction> {
+        let raw = tx.raw();
+        let inputs = raw
+            .inputs()
+            .into_iter()
+            .map(|input| input.previous_output());
+        let cell_deps = raw.cell_deps().into_iter().map(|dep| dep.out_point());
+        if let Some(out_point) = inputs.chain(cell_deps).find(|op| !self.is_live(op)) {
+            return Err(Error::Unresolvable(format!("dead cell {}", out_point)));
+        }
+        resolve_transaction(self.network, self, tx)
+    }
+}
+
+impl CellProvider for MockChain {
+    fn cell(&self, out_point: &packed::OutPoint) -> Option<(packed::CellOutput, Bytes)> {
+        if self.spent.contains(out_point) {
+            return None;
+        }
+        self.cells
+            .get(out_point)
+            .map(|cell| (cell.output.clone(), cell.data.clone()))
+    }
+
+    fn is_spent(&self, out_point: &packed::OutPoint) -> bool {
+        self.spent.contains(out_point)
+    }
+
+    fn cell_header(&self, out_point: &packed::OutPoint) -> Option<HeaderView> {
+        self.cells
+            .get(out_point)
+            .and_then(|cell| self.headers.get(&cell.block_hash))
+            .cloned()
+    }
+
+    fn header(&self, block_hash: &packed::Byte32) -> Option<HeaderView> {
+        self.headers.get(block_hash).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ckb_types::{
+        bytes::Bytes,
+        core::{BlockView, Capacity, TransactionBuilder},
+        packed,
+        prelude::*,
+    };
+
+    use super::MockChain;
+    use crate::{
+        blockchain::Network,
+        dev_accounts::DevAccounts,
+        error::Error,
+        resolve::{find_cell, resolve_transaction},
+    };
+
+    fn spend(out_point: &packed::OutPoint) -> packed::Transaction {
+        TransactionBuilder::default()
+            .input(packed::CellInput::new(out_point.clone(), 0))
+            .build()
+            .data()
+    }
+
+    #[test]
+    fn spent_genesis_cell_is_not_resolved() {
+        let mut chain = MockChain::new(Network::Develop);
+        let out_point = DevAccounts::read().accounts()[0].out_point().clone();
+        assert!(find_cell(Network::Develop, &chain, &out_point).is_some());
+        let tx = spend(&out_point);
+        chain.mine(&[tx.clone()]).unwrap();
+        assert!(chain.is_spent(&out_point));
+        assert!(find_cell(Network::Develop, &chain, &out_point).is_none());
+        assert!(chain.resolve(&tx).is_err());
+        assert!(resolve_transaction(Network::Develop, &chain, &tx).is_err());
+    }
+
+    #[test]
+    fn advance_epochs() {
+        let mut chain = MockChain::new(Network::Develop);
+        let parent = chain.tip().clone();
+        let header = chain.advance_epochs(3).unwrap();
+        assert_eq!(header.parent_hash(), parent.hash());
+        assert_eq!(header.number(), parent.number() + 1);
+        assert_eq!(header.epoch().number(), parent.epoch().number() + 3);
+        assert_eq!(header.epoch().index(), 0);
+        assert_eq!(chain.tip().hash(), header.hash());
+        assert!(chain.get_header(&header.hash()).is_some());
+        let result = chain.advance_epochs(0);
+        assert!(matches!(result, Err(Error::InvalidBlock(_))));
+        assert_eq!(chain.tip().hash(), header.hash());
+    }
+
+    #[test]
+    fn add_cell_and_resolve() {
+        let mut chain = MockChain::new(Network::Develop);
+        let output = packed::CellOutput::new_builder()
+            .capacity(Capacity::shannons(100_000_000_000).pack())
+            .build();
+        let data = Bytes::from(vec![1, 2, 3]);
+        let out_point = chain.add_cell(output.clone(), data.clone());
+        let another = chain.add_cell(output.clone(), data.clone());
+        assert_ne!(out_point, another);
+        assert!(chain.is_live(&out_point));
+        let resolved = chain.resolve(&spend(&out_point)).unwrap();
+        let cell = &resolved.resolved_inputs[0];
+        assert_eq!(cell.cell_output, output);
+        assert_eq!(cell.mem_cell_data.as_ref().map(|(d, _)| d), Some(&data));
+        let info = cell.transaction_info.as_ref().unwrap();
+        assert_eq!(info.block_hash, chain.tip().hash());
+    }
+
+    #[test]
+    fn add_block_of_other_parent() {
+        let mut chain = MockChain::new(Network::Develop);
+        let tip = chain.tip().clone();
+        let block = BlockView::new_advanced_builder()
+            .number((tip.number() + 1).pack())
+            .build();
+        let result = chain.add_block(&block);
+        assert!(matches!(result, Err(Error::InvalidBlock(_))));
+        assert_eq!(chain.tip().hash(), tip.hash());
+    }
+
+    #[test]
+    fn add_block_with_double_spending() {
+        let mut chain = MockChain::new(Network::Develop);
+        let out_point = DevAccounts::read().accounts()[0].out_point().clone();
+        let tx = spend(&out_point);
+        let another = tx
+            .clone()
+            .as_builder()
+            .witnesses(vec![Bytes::from(vec![0]).pack()].pack())
+            .build();
+        let block = chain.build_block(chain.next_epoch(), &[tx.clone(), another]);
+        let result = chain.add_block(&block);
+        assert!(matches!(result, Err(Error::InvalidBlock(_))));
+        assert!(chain.is_live(&out_point));
+        chain.mine(&[tx.clone()]).unwrap();
+        let result = chain.mine(&[tx]);
+        assert!(matches!(result, Err(Error::InvalidBlock(_))));
+    }
+}
@@ -0,0 +1,326 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::{
+    collections::HashMap,
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use serde_json::{json, Value};
+
+use ckb_jsonrpc_types::{BlockView, ChainInfo, Consensus, ProposalWindow};
+use ckb_types::{core::RationalU256, prelude::*, utilities::compact_to_difficulty, H256, U256};
+
+use crate::{
+    blockchain::{Bundled, Network},
+    consensus::ConsensusParams,
+    error::Result,
+    genesis::Genesis,
+    http,
+    system_deps::SystemDeps,
+};
+
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const PARSE_ERROR: i64 = -32700;
+
+/// Connections are served one by one, so an idle client should not block the others forever.
+const STREAM_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A local JSON-RPC server which answers the startup queries of clients from the bundled
+/// genesis block and consensus params of a network.
+///
+/// The supported methods are `get_blockchain_info`, `get_block_by_number`, `get_block_hash`
+/// and `get_consensus`, only the genesis block is known.
+///
+/// The server is stopped when it is dropped.
+pub struct MockRpcServer {
+    local_addr: SocketAddr,
+    stopped: Arc<AtomicBool>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+struct MockRpc {
+    network: Network,
+    overrides: HashMap<String, Value>,
+}
+
+fn parse_block_number(params: &Value) -> ::std::result::Result<u64, (i64, String)> {
+    params
+        .get(0)
+        .and_then(Value::as_str)
+        .and_then(|s| s.strip_prefix("0x"))
+        .and_then(|s| u64::from_str_radix(s, 16).ok())
+        .ok_or_else(|| {
+            (
+                INVALID_PARAMS,
+                format!("invalid block number in {}", params),
+            )
+        })
+}
+
+impl MockRpc {
+    fn get_blockchain_info(&self) -> ChainInfo {
+        let consensus = ConsensusParams::read().lookup(self.network);
+        let header = Genesis::read().lookup(self.network).header();
+        ChainInfo {
+            chain: consensus.id().to_owned(),
+            median_time: header.timestamp().into(),
+            epoch: header.epoch().full_value().into(),
+            difficulty: compact_to_difficulty(header.compact_target()),
+            is_initial_block_download: false,
+            alerts: Vec::new(),
+        }
+    }
+
+    fn get_consensus(&self) -> Consensus {
+        let consensus = ConsensusParams::read().lookup(self.network);
+        let system_deps = SystemDeps::read();
+        let type_hash = |bundled| {
+            system_deps
+                .lookup_system_cell(self.network, bundled)
+                .ok()
+                .and_then(|cell| cell.type_hash().clone())
+        };
+        let proposer_reward_ratio = consensus.proposer_reward_ratio();
+        Consensus {
+            id: consensus.id().to_owned(),
+            genesis_hash: consensus.genesis_hash().clone(),
+            dao_type_hash: type_hash(Bundled::Dao),
+            secp256k1_blake160_sighash_all_type_hash: type_hash(Bundled::Secp256k1Blake160),
+            secp256k1_blake160_multisig_all_type_hash: type_hash(
+                Bundled::Secp256k1Blake160MultiSig,
+            ),
+            initial_primary_epoch_reward: consensus.initial_primary_epoch_reward().as_u64().into(),
+            secondary_epoch_reward: consensus.secondary_epoch_reward().as_u64().into(),
+            max_uncles_num: (consensus.max_uncles_num() as u64).into(),
+            orphan_rate_target: consensus.orphan_rate_target().clone(),
+            epoch_duration_target: consensus.epoch_duration_target().into(),
+            tx_proposal_window: ProposalWindow {
+                closest: consensus.tx_proposal_window_closest().into(),
+                farthest: consensus.tx_proposal_window_farthest().into(),
+            },
+            proposer_reward_ratio: RationalU256::new_raw(
+                U256::from(proposer_reward_ratio.numer()),
+                U256::from(proposer_reward_ratio.denom()),
+            ),
+            cellbase_maturity: consensus.cellbase_maturity().full_value().into(),
+            median_time_block_count: (consensus.median_time_block_count() as u64).into(),
+            max_block_cycles: consensus.max_block_cycles().into(),
+            max_block_bytes: consensus.max_block_bytes().into(),
+            block_version: consensus.block_version().into(),
+            tx_version: consensus.tx_version().into(),
+            type_id_code_hash: consensus.type_id_code_hash().clone(),
+            max_block_proposals_limit: consensus.max_block_proposals_limit().into(),
+            primary_epoch_reward_halving_interval: consensus
+                .primary_epoch_reward_halving_interval()
+                .into(),
+            permanent_difficulty_in_dummy: consensus.permanent_difficulty_in_dummy(),
+        }
+    }
+
+    fn call(&self, method: &str, params: &Value) -> ::std::result::Result<Value, (i64, String)> {
+        if let Some(result) = self.overrides.get(method) {
+            return Ok(result.clone());
+        }
+        let genesis = Genesis::read().lookup(self.network);
+        match method {
+            "get_blockchain_info" => Ok(json!(self.get_blockchain_info())),
+            "get_consensus" => Ok(json!(self.get_consensus())),
+            "get_block_hash" => {
+                if parse_block_number(params)? == 0 {
+                    let hash: H256 = genesis.hash().unpack();
+                    Ok(json!(hash))
+                } else {
+                    Ok(Value::Null)
+                }
+            }
+            "get_block_by_number" => {
+                if parse_block_number(params)? == 0 {
+                    let block: BlockView = genesis.clone().into();
+                    Ok(json!(block))
+                } else {
+                    Ok(Value::Null)
+                }
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("method '{}' not found", method))),
+        }
+    }
+
+    fn handle(&self, body: &[u8]) -> Value {
+        let request: Value = match serde_json::from_slice(body) {
+            Ok(request) => request,
+            Err(err) => {
+                return json!({
+                    "jsonrpc": "2.0",
+                    "id": Value::Null,
+                    "error": { "code": PARSE_ERROR, "message": err.to_string() },
+                });
+            }
+        };
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let method = request
+            .get("method")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let params = request.get("params").cloned().unwrap_or_else(|| json!([]));
+        match self.call(method, &params) {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message },
+            }),
+        }
+    }
+
+    fn serve(&self, mut stream: TcpStream) -> ::std::io::Result<()> {
+        stream.set_read_timeout(Some(STREAM_TIMEOUT))?;
+        stream.set_write_timeout(Some(STREAM_TIMEOUT))?;
+        let (_, body) = http::read_message(&mut stream)?;
+        let response = serde_json::to_vec(&self.handle(&body))?;
+        http::write_response(&mut stream, "200 OK", &response)
+    }
+}
+
+impl MockRpcServer {
+    /// Starts a server on a random local port.
+    pub fn start(network: Network) -> Result<Self> {
+        Self::start_with_overrides(network, HashMap::new())
+    }
+
+    /// Starts a server which answers some methods with the provided results, instead of the
+    /// bundled data.
+    pub fn start_with_overrides(
+        network: Network,
+        overrides: HashMap<String, Value>,
+    ) -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let local_addr = listener.local_addr()?;
+        let stopped = Arc::new(AtomicBool::new(false));
+        let rpc = MockRpc { network, overrides };
+        let handle = {
+            let stopped = Arc::clone(&stopped);
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stopped.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        let _ = rpc.serve(stream);
+                    }
+                }
+            })
+        };
+        Ok(Self {
+            local_addr,
+            stopped,
+            handle: Some(handle),
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}/", self.local_addr)
+    }
+}
+
+impl Drop for MockRpcServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Wake up the listener which is blocked on accepting.
+        let _ = TcpStream::connect(self.local_addr);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde::de::DeserializeOwned;
+    use serde_json::{json, Value};
+
+    use ckb_jsonrpc_types::{BlockView, ChainInfo, Consensus};
+    use ckb_types::{prelude::*, H256};
+
+    use super::MockRpcServer;
+    use crate::{
+        blockchain::{Bundled, Network},
+        consensus::ConsensusParams,
+        genesis::Genesis,
+        http,
+        system_deps::SystemDeps,
+    };
+
+    fn call(server: &MockRpcServer, method: &str, params: Value) -> Value {
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let body = serde_json::to_vec(&request).unwrap();
+        let response = http::post_json(&server.url(), &body, Duration::from_secs(5)).unwrap();
+        serde_json::from_slice(&response).unwrap()
+    }
+
+    fn call_for<T: DeserializeOwned>(server: &MockRpcServer, method: &str, params: Value) -> T {
+        let response = call(server, method, params);
+        serde_json::from_value(response["result"].clone()).unwrap()
+    }
+
+    #[test]
+    fn get_blockchain_info_and_consensus() {
+        for network in Network::all() {
+            let server = MockRpcServer::start(*network).unwrap();
+            let params = ConsensusParams::read().lookup(*network);
+            let info: ChainInfo = call_for(&server, "get_blockchain_info", json!([]));
+            assert_eq!(&info.chain, params.id());
+            assert!(!info.is_initial_block_download);
+            let consensus: Consensus = call_for(&server, "get_consensus", json!([]));
+            assert_eq!(&consensus.id, params.id());
+            assert_eq!(&consensus.genesis_hash, params.genesis_hash());
+            assert_eq!(&consensus.orphan_rate_target, params.orphan_rate_target());
+            assert_eq!(&consensus.type_id_code_hash, params.type_id_code_hash());
+            let dao = SystemDeps::read()
+                .lookup_system_cell(*network, Bundled::Dao)
+                .unwrap();
+            assert_eq!(&consensus.dao_type_hash, dao.type_hash());
+        }
+    }
+
+    #[test]
+    fn get_genesis_block() {
+        let server = MockRpcServer::start(Network::Testnet).unwrap();
+        let genesis = Genesis::read().lookup(Network::Testnet);
+        let hash: Option<H256> = call_for(&server, "get_block_hash", json!(["0x0"]));
+        assert_eq!(hash, Some(genesis.hash().unpack()));
+        let block: Option<BlockView> = call_for(&server, "get_block_by_number", json!(["0x0"]));
+        assert_eq!(block.unwrap().header.hash, genesis.hash().unpack());
+        let hash: Option<H256> = call_for(&server, "get_block_hash", json!(["0x1"]));
+        assert!(hash.is_none());
+        let block: Option<BlockView> = call_for(&server, "get_block_by_number", json!(["0x1"]));
+        assert!(block.is_none());
+    }
+
+    #[test]
+    fn unknown_method_and_invalid_params() {
+        let server = MockRpcServer::start(Network::Develop).unwrap();
+        let response = call(&server, "get_tip_header", json!([]));
+        assert_eq!(response["error"]["code"], json!(super::METHOD_NOT_FOUND));
+        let response = call(&server, "get_block_hash", json!([0]));
+        assert_eq!(response["error"]["code"], json!(super::INVALID_PARAMS));
+    }
+}
@@ -0,0 +1,173 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::HashMap;
+
+use property::Property;
+use serde::{Deserialize, Deserializer};
+use serde_derive::Deserialize;
+
+use ckb_types::{
+    core::{Capacity, FeeRate},
+    packed,
+};
+
+use crate::{blockchain::Network, error::Result};
+
+lazy_static::lazy_static! {
+    static ref NODE_CONFIGS: NodeConfigs = NodeConfigs::initialize().unwrap();
+}
+
+pub struct NodeConfigs(HashMap<Network, NodeConfig>);
+
+#[derive(Property, Deserialize, Debug, Clone)]
+#[property(get(public), set(disable), mut(disable))]
+pub struct NodeConfig {
+    network: NetworkConfig,
+    rpc: RpcConfig,
+    tx_pool: TxPoolConfig,
+    store: StoreConfig,
+}
+
+#[derive(Property, Deserialize, Debug, Clone)]
+#[property(get(public), set(disable), mut(disable))]
+pub struct NetworkConfig {
+    listen_addresses: Vec<String>,
+    max_peers: u32,
+    max_outbound_peers: u32,
+    ping_interval_secs: u64,
+    ping_timeout_secs: u64,
+    connect_outbound_interval_secs: u64,
+    upnp: bool,
+    discovery_local_address: bool,
+    bootnode_mode: bool,
+}
+
+#[derive(Property, Deserialize, Debug, Clone)]
+#[property(get(public), set(disable), mut(disable))]
+pub struct RpcConfig {
+    listen_address: String,
+    max_request_body_size: usize,
+    modules: Vec<String>,
+}
+
+#[derive(Property, Deserialize, Debug, Clone)]
+#[property(get(public), set(disable), mut(disable))]
+pub struct TxPoolConfig {
+    max_mem_size: usize,
+    max_cycles: u64,
+    #[serde(deserialize_with = "deserialize_fee_rate")]
+    min_fee_rate: FeeRate,
+    max_tx_verify_cycles: u64,
+    max_ancestors_count: usize,
+}
+
+#[derive(Property, Deserialize, Debug, Clone)]
+#[property(get(public), set(disable), mut(disable))]
+pub struct StoreConfig {
+    header_cache_size: usize,
+    cell_data_cache_size: usize,
+    block_proposals_cache_size: usize,
+    block_tx_hashes_cache_size: usize,
+    block_uncles_cache_size: usize,
+    cellbase_cache_size: usize,
+}
+
+fn deserialize_fee_rate<'de, D>(deserializer: D) -> ::std::result::Result<FeeRate, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    u64::deserialize(deserializer).map(FeeRate::from_u64)
+}
+
+fn load_config_content(network: Network) -> &'static str {
+    match network {
+        Network::Mainnet => include_str!(concat!(env!("OUT_DIR"), "/mainnet/ckb.toml")),
+        Network::Testnet => include_str!(concat!(env!("OUT_DIR"), "/testnet/ckb.toml")),
+        Network::Staging => include_str!(concat!(env!("OUT_DIR"), "/staging/ckb.toml")),
+        Network::Develop => include_str!(concat!(env!("OUT_DIR"), "/develop/ckb.toml")),
+    }
+}
+
+impl TxPoolConfig {
+    /// The minimal fee which a transaction should pay to be accepted by the default tx pool.
+    pub fn min_fee(&self, tx: &packed::Transaction) -> Capacity {
+        self.min_fee_rate.fee(tx.serialized_size_in_block())
+    }
+
+    /// Checks whether a transaction with the provided fee could be accepted by the default tx
+    /// pool, only fee rate and cycles are checked.
+    pub fn accepts(&self, tx: &packed::Transaction, fee: Capacity, cycles: u64) -> bool {
+        fee >= self.min_fee(tx) && cycles <= self.max_tx_verify_cycles
+    }
+}
+
+impl NodeConfigs {
+    pub fn read() -> &'static Self {
+        &NODE_CONFIGS
+    }
+
+    fn initialize() -> Result<Self> {
+        let mut configs = Self(HashMap::new());
+        configs.initialize_network(Network::Mainnet)?;
+        configs.initialize_network(Network::Testnet)?;
+        configs.initialize_network(Network::Staging)?;
+        configs.initialize_network(Network::Develop)?;
+        Ok(configs)
+    }
+
+    fn initialize_network(&mut self, network: Network) -> Result<()> {
+        let config = toml::from_str(load_config_content(network))?;
+        self.0.insert(network, config);
+        Ok(())
+    }
+
+    pub fn lookup(&self, network: Network) -> &NodeConfig {
+        self.0.get(&network).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ckb_types::{
+        core::{Capacity, TransactionBuilder},
+        packed,
+    };
+
+    use super::NodeConfigs;
+    use crate::blockchain::Network;
+
+    #[test]
+    fn load_all_networks() {
+        let configs = NodeConfigs::initialize().unwrap();
+        for network in Network::all() {
+            let config = configs.lookup(*network);
+            assert!(!config.rpc().listen_address().is_empty());
+        }
+        let mainnet = configs.lookup(Network::Mainnet);
+        assert_eq!(mainnet.tx_pool().min_fee_rate().as_u64(), 1000);
+    }
+
+    #[test]
+    fn accepts_by_fee_rate_and_cycles() {
+        let tx_pool = NodeConfigs::read().lookup(Network::Mainnet).tx_pool();
+        let tx = TransactionBuilder::default()
+            .input(packed::CellInput::new(packed::OutPoint::default(), 0))
+            .build()
+            .data();
+        let min_fee = tx_pool.min_fee(&tx);
+        // 1000 shannons per KB, which is one shannon per byte.
+        let size = tx.serialized_size_in_block() as u64;
+        assert_eq!(min_fee, Capacity::shannons(size));
+        let max_cycles = tx_pool.max_tx_verify_cycles();
+        assert!(tx_pool.accepts(&tx, min_fee, max_cycles));
+        let less = Capacity::shannons(min_fee.as_u64() - 1);
+        assert!(!tx_pool.accepts(&tx, less, max_cycles));
+        assert!(!tx_pool.accepts(&tx, min_fee, max_cycles + 1));
+    }
+}
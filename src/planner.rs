@@ -0,0 +1,155 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::BTreeSet;
+
+use ckb_types::packed;
+
+use crate::{
+    blockchain::{Bundled, DepGroupId, Network},
+    error::Result,
+    system_deps::SystemDeps,
+};
+
+/// The system cells which should be loaded when a script runs.
+fn requirements(bundled: Bundled) -> &'static [Bundled] {
+    match bundled {
+        Bundled::Secp256k1Blake160 => &[Bundled::Secp256k1Blake160, Bundled::Secp256k1Data],
+        Bundled::Secp256k1Blake160MultiSig => {
+            &[Bundled::Secp256k1Blake160MultiSig, Bundled::Secp256k1Data]
+        }
+        Bundled::Secp256k1Data => &[Bundled::Secp256k1Data],
+        Bundled::Dao => &[Bundled::Dao],
+    }
+}
+
+impl SystemDeps {
+    /// Plans the smallest set of cell deps for the system scripts which are used by a
+    /// transaction.
+    ///
+    /// Dep groups are preferred when they cover multiple requirements, and code cells are
+    /// preferred when they have the same count of cell deps.
+    pub fn plan_cell_deps(
+        &self,
+        network: Network,
+        scripts: &[Bundled],
+    ) -> Result<Vec<packed::CellDep>> {
+        let spec = self.lookup_spec(network)?;
+        let needed = scripts
+            .iter()
+            .flat_map(|bundled| requirements(*bundled).iter().cloned())
+            .collect::<BTreeSet<_>>();
+        let candidates = {
+            let mut candidates = spec
+                .dep_groups()
+                .iter()
+                .map(|(dep_group_id, dep_group)| {
+                    let included = dep_group
+                        .included_cells()
+                        .into_iter()
+                        .collect::<BTreeSet<_>>();
+                    (*dep_group_id, included)
+                })
+                .filter(|(_, included)| !included.is_disjoint(&needed))
+                .collect::<Vec<_>>();
+            candidates.sort_by_key(|(dep_group_id, _)| *dep_group_id);
+            candidates
+        };
+        let mut best: Option<(Vec<DepGroupId>, BTreeSet<Bundled>)> = None;
+        for mask in 0..(1usize << candidates.len()) {
+            let mut dep_groups = Vec::new();
+            let mut remaining = needed.clone();
+            for (i, (dep_group_id, included)) in candidates.iter().enumerate() {
+                if mask & (1 << i) != 0 {
+                    dep_groups.push(*dep_group_id);
+                    remaining = remaining.difference(included).cloned().collect();
+                }
+            }
+            let is_better = best.as_ref().map_or(true, |(best_groups, best_remaining)| {
+                let count = dep_groups.len() + remaining.len();
+                let best_count = best_groups.len() + best_remaining.len();
+                count < best_count || (count == best_count && dep_groups.len() < best_groups.len())
+            });
+            if is_better {
+                best = Some((dep_groups, remaining));
+            }
+        }
+        let (dep_groups, code_cells) = best.unwrap_or_default();
+        let mut cell_deps = Vec::with_capacity(dep_groups.len() + code_cells.len());
+        for dep_group_id in dep_groups {
+            cell_deps.push(self.lookup_dep_group(network, dep_group_id)?.cell_dep());
+        }
+        for bundled in code_cells {
+            cell_deps.push(self.lookup_system_cell(network, bundled)?.cell_dep());
+        }
+        Ok(cell_deps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        blockchain::{Bundled, DepGroupId, Network},
+        system_deps::SystemDeps,
+    };
+
+    #[test]
+    fn plan_single_script() {
+        let system_deps = SystemDeps::read();
+        for network in Network::all() {
+            let cell_deps = system_deps
+                .plan_cell_deps(*network, &[Bundled::Secp256k1Blake160])
+                .unwrap();
+            let dep_group = system_deps
+                .lookup_dep_group(*network, DepGroupId::Secp256k1Blake160)
+                .unwrap();
+            assert_eq!(cell_deps, vec![dep_group.cell_dep()]);
+            let cell_deps = system_deps
+                .plan_cell_deps(*network, &[Bundled::Dao])
+                .unwrap();
+            let dao = system_deps
+                .lookup_system_cell(*network, Bundled::Dao)
+                .unwrap();
+            assert_eq!(cell_deps, vec![dao.cell_dep()]);
+        }
+    }
+
+    #[test]
+    fn plan_multiple_scripts() {
+        let system_deps = SystemDeps::read();
+        let network = Network::Mainnet;
+        let scripts = [
+            Bundled::Secp256k1Blake160,
+            Bundled::Secp256k1Blake160MultiSig,
+        ];
+        let cell_deps = system_deps.plan_cell_deps(network, &scripts).unwrap();
+        // Both dep groups or one dep group and a code cell, the latter is preferred.
+        let dep_group = system_deps
+            .lookup_dep_group(network, DepGroupId::Secp256k1Blake160)
+            .unwrap();
+        let multisig = system_deps
+            .lookup_system_cell(network, Bundled::Secp256k1Blake160MultiSig)
+            .unwrap();
+        assert_eq!(cell_deps, vec![dep_group.cell_dep(), multisig.cell_dep()]);
+        let scripts = [
+            Bundled::Secp256k1Blake160,
+            Bundled::Dao,
+            Bundled::Secp256k1Blake160,
+        ];
+        let cell_deps = system_deps.plan_cell_deps(network, &scripts).unwrap();
+        assert_eq!(cell_deps.len(), 2);
+    }
+
+    #[test]
+    fn plan_nothing() {
+        let cell_deps = SystemDeps::read()
+            .plan_cell_deps(Network::Testnet, &[])
+            .unwrap();
+        assert!(cell_deps.is_empty());
+    }
+}
@@ -0,0 +1,276 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::{
+    net::{IpAddr, SocketAddr},
+    time::{Duration, Instant},
+};
+
+use property::Property;
+use tentacle::secio::{handshake::Config as SecioConfig, PeerId, SecioKeyPair};
+use tokio::{net::TcpStream, runtime, time};
+
+use crate::{
+    blockchain::Network,
+    bootnodes::{BootNode, BootNodes, Host, Transport},
+    error::Result,
+};
+
+#[derive(Property, Debug, Clone)]
+#[property(get(public), set(disable), mut(disable))]
+pub struct ProbeOptions {
+    timeout: Duration,
+    handshake: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum ProbeStatus {
+    /// The tcp endpoint accepted the connection.
+    Reachable,
+    /// The secio handshake was completed and the remote peer id is the advertised one.
+    Verified,
+    /// The secio handshake was completed but the remote peer id is not the advertised one.
+    PeerIdMismatch(PeerId),
+    Unresolved(String),
+    Unreachable(String),
+    HandshakeFailed(String),
+    Timeout,
+}
+
+#[derive(Property, Debug, Clone)]
+#[property(get(public), set(disable), mut(disable))]
+pub struct ProbeReport {
+    node: BootNode,
+    socket_addr: Option<SocketAddr>,
+    elapsed: Duration,
+    status: ProbeStatus,
+}
+
+impl Default for ProbeOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(10),
+            handshake: false,
+        }
+    }
+}
+
+impl ProbeOptions {
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Completes the secio handshake after the tcp connection was established, to confirm the
+    /// advertised peer id.
+    ///
+    /// The handshake is skipped for bootnodes over websocket.
+    pub fn with_handshake(mut self, handshake: bool) -> Self {
+        self.handshake = handshake;
+        self
+    }
+}
+
+impl ProbeStatus {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Self::Reachable | Self::Verified)
+    }
+}
+
+async fn resolve(node: &BootNode) -> ::std::result::Result<SocketAddr, String> {
+    let port = node.port();
+    match node.host() {
+        Host::Ip4(ip) => Ok(SocketAddr::new(IpAddr::V4(*ip), port)),
+        Host::Ip6(ip) => Ok(SocketAddr::new(IpAddr::V6(*ip), port)),
+        Host::Dns4(name) | Host::Dns6(name) => {
+            let want_ipv4 = matches!(node.host(), Host::Dns4(_));
+            tokio::net::lookup_host((name.as_str(), port))
+                .await
+                .map_err(|err| err.to_string())?
+                .find(|addr| addr.is_ipv4() == want_ipv4)
+                .ok_or_else(|| format!("no address was found for {}", name))
+        }
+    }
+}
+
+async fn check(node: &BootNode, socket_addr: SocketAddr, handshake: bool) -> ProbeStatus {
+    let stream = match TcpStream::connect(socket_addr).await {
+        Ok(stream) => stream,
+        Err(err) => return ProbeStatus::Unreachable(err.to_string()),
+    };
+    if !handshake || !matches!(node.transport(), Transport::Tcp) {
+        return ProbeStatus::Reachable;
+    }
+    let key_pair = SecioKeyPair::secp256k1_generated();
+    match SecioConfig::new(key_pair).handshake(stream).await {
+        Ok((_, remote_pubkey, _)) => {
+            let remote_peer_id = remote_pubkey.peer_id();
+            if &remote_peer_id == node.peer_id() {
+                ProbeStatus::Verified
+            } else {
+                ProbeStatus::PeerIdMismatch(remote_peer_id)
+            }
+        }
+        Err(err) => ProbeStatus::HandshakeFailed(err.to_string()),
+    }
+}
+
+async fn probe_node(node: &BootNode, options: &ProbeOptions) -> ProbeReport {
+    let start = Instant::now();
+    let (socket_addr, status) = match time::timeout(options.timeout, resolve(node)).await {
+        Err(_) => (None, ProbeStatus::Timeout),
+        Ok(Err(err)) => (None, ProbeStatus::Unresolved(err)),
+        Ok(Ok(socket_addr)) => {
+            let remaining = options
+                .timeout
+                .checked_sub(start.elapsed())
+                .unwrap_or_default();
+            let checked = time::timeout(remaining, check(node, socket_addr, options.handshake));
+            let status = checked.await.unwrap_or(ProbeStatus::Timeout);
+            (Some(socket_addr), status)
+        }
+    };
+    ProbeReport {
+        node: node.clone(),
+        socket_addr,
+        elapsed: start.elapsed(),
+        status,
+    }
+}
+
+fn probe_nodes(nodes: &[BootNode], options: &ProbeOptions) -> Result<Vec<ProbeReport>> {
+    let mut runtime = runtime::Builder::new()
+        .basic_scheduler()
+        .enable_all()
+        .build()?;
+    let probes = nodes.iter().map(|node| probe_node(node, options));
+    let reports = runtime.block_on(futures::future::join_all(probes));
+    Ok(reports)
+}
+
+impl BootNode {
+    /// Dials the tcp endpoint of the bootnode.
+    pub fn probe(&self, options: &ProbeOptions) -> Result<ProbeReport> {
+        probe_nodes(::std::slice::from_ref(self), options).map(|mut reports| reports.remove(0))
+    }
+}
+
+impl BootNodes {
+    /// Dials all bootnodes of a network concurrently, one report for each bootnode.
+    pub fn probe(&self, network: Network, options: &ProbeOptions) -> Result<Vec<ProbeReport>> {
+        probe_nodes(self.lookup_nodes(network), options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::TcpListener, thread, time::Duration};
+
+    use tentacle::secio::{handshake::Config as SecioConfig, PeerId, SecioKeyPair};
+    use tokio::runtime;
+
+    use super::{ProbeOptions, ProbeStatus};
+    use crate::bootnodes::BootNode;
+
+    fn local_node(port: u16, peer_id: &PeerId) -> BootNode {
+        format!("/ip4/127.0.0.1/tcp/{}/p2p/{}", port, peer_id.to_base58())
+            .parse()
+            .unwrap()
+    }
+
+    fn random_peer_id() -> PeerId {
+        SecioKeyPair::secp256k1_generated().public_key().peer_id()
+    }
+
+    fn options() -> ProbeOptions {
+        ProbeOptions::default().with_timeout(Duration::from_secs(5))
+    }
+
+    /// Starts a listener which completes the secio handshake for one connection.
+    fn start_responder(key_pair: SecioKeyPair) -> (u16, thread::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        listener.set_nonblocking(true).unwrap();
+        let handle = thread::spawn(move || {
+            let mut runtime = runtime::Builder::new()
+                .basic_scheduler()
+                .enable_all()
+                .build()
+                .unwrap();
+            runtime.block_on(async move {
+                let mut listener = tokio::net::TcpListener::from_std(listener).unwrap();
+                let (stream, _) = listener.accept().await.unwrap();
+                let _ = SecioConfig::new(key_pair).handshake(stream).await;
+            });
+        });
+        (port, handle)
+    }
+
+    #[test]
+    fn reachable() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let report = local_node(port, &random_peer_id())
+            .probe(&options())
+            .unwrap();
+        assert!(matches!(report.status(), ProbeStatus::Reachable));
+        assert!(report.status().is_ok());
+    }
+
+    #[test]
+    fn unreachable() {
+        let port = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        let report = local_node(port, &random_peer_id())
+            .probe(&options())
+            .unwrap();
+        assert!(matches!(report.status(), ProbeStatus::Unreachable(_)));
+        assert!(!report.status().is_ok());
+    }
+
+    #[test]
+    fn timeout() {
+        // The connection is queued by the kernel, but the handshake is never answered.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let options = ProbeOptions::default()
+            .with_timeout(Duration::from_millis(200))
+            .with_handshake(true);
+        let report = local_node(port, &random_peer_id()).probe(&options).unwrap();
+        assert!(matches!(report.status(), ProbeStatus::Timeout));
+    }
+
+    #[test]
+    fn verified() {
+        let key_pair = SecioKeyPair::secp256k1_generated();
+        let peer_id = key_pair.public_key().peer_id();
+        let (port, handle) = start_responder(key_pair);
+        let report = local_node(port, &peer_id)
+            .probe(&options().with_handshake(true))
+            .unwrap();
+        handle.join().unwrap();
+        assert!(matches!(report.status(), ProbeStatus::Verified));
+    }
+
+    #[test]
+    fn peer_id_mismatch() {
+        let key_pair = SecioKeyPair::secp256k1_generated();
+        let peer_id = key_pair.public_key().peer_id();
+        let (port, handle) = start_responder(key_pair);
+        let report = local_node(port, &random_peer_id())
+            .probe(&options().with_handshake(true))
+            .unwrap();
+        handle.join().unwrap();
+        match report.status() {
+            ProbeStatus::PeerIdMismatch(remote) => assert_eq!(remote, &peer_id),
+            status => panic!("unexpected status {:?}", status),
+        }
+    }
+}
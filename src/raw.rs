@@ -0,0 +1,55 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The serialized format of the spec hashes, it's shared with the build script.
+
+use std::collections::BTreeMap;
+
+use serde_derive::{Deserialize, Serialize};
+
+use ckb_types::H256;
+
+/// The version of the serialized format, it should be increased for incompatible changes.
+pub(crate) const FORMAT_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct RawSystemDeps {
+    pub(crate) version: u32,
+    pub(crate) specs: BTreeMap<String, RawSpecHashes>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct RawSpecHashes {
+    pub(crate) genesis: H256,
+    pub(crate) cellbase: H256,
+    pub(crate) system_cells: Vec<RawSystemCell>,
+    pub(crate) dep_groups: Vec<RawDepGroup>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct RawSystemCell {
+    pub(crate) path: String,
+    pub(crate) tx_hash: H256,
+    pub(crate) index: usize,
+    pub(crate) data_hash: H256,
+    pub(crate) type_hash: Option<H256>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct RawDepGroup {
+    pub(crate) included_cells: Vec<String>,
+    pub(crate) tx_hash: H256,
+    pub(crate) index: usize,
+    pub(crate) members: Vec<RawMemberCell>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct RawMemberCell {
+    pub(crate) tx_hash: H256,
+    pub(crate) index: usize,
+}
@@ -0,0 +1,151 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[cfg(any(feature = "verifier", feature = "test-support"))]
+use std::convert::TryFrom;
+
+use ckb_types::{bytes::Bytes, core::HeaderView, packed};
+#[cfg(any(feature = "verifier", feature = "test-support"))]
+use ckb_types::{
+    core::{
+        cell::{CellMeta, CellMetaBuilder, ResolvedTransaction},
+        DepType, TransactionInfo,
+    },
+    prelude::*,
+};
+
+#[cfg(any(feature = "verifier", feature = "test-support"))]
+use crate::{
+    blockchain::Network,
+    error::{Error, Result},
+    genesis::Genesis,
+};
+
+/// Provides the live cells and the headers which are not in the genesis block.
+pub trait CellProvider {
+    /// Finds the output and the data of a cell.
+    fn cell(&self, out_point: &packed::OutPoint) -> Option<(packed::CellOutput, Bytes)>;
+
+    /// Checks whether a cell has been spent.
+    ///
+    /// The spent cells are never resolved, even if they are in the genesis block.
+    fn is_spent(&self, _out_point: &packed::OutPoint) -> bool {
+        false
+    }
+
+    /// Finds the header of the block which a cell was created in.
+    ///
+    /// It's only required by the scripts which load the headers of inputs, such as the DAO.
+    fn cell_header(&self, _out_point: &packed::OutPoint) -> Option<HeaderView> {
+        None
+    }
+
+    /// Finds a header by its hash, for the header deps.
+    fn header(&self, _block_hash: &packed::Byte32) -> Option<HeaderView> {
+        None
+    }
+}
+
+/// Finds a cell in the provider at first, then in the genesis block of the network.
+///
+/// Returns `None` if the provider reports the cell as spent.
+#[cfg(any(feature = "verifier", feature = "test-support"))]
+pub(crate) fn find_cell<P: CellProvider>(
+    network: Network,
+    provider: &P,
+    out_point: &packed::OutPoint,
+) -> Option<(packed::CellOutput, Bytes, Option<HeaderView>)> {
+    if provider.is_spent(out_point) {
+        None
+    } else if let Some((output, data)) = provider.cell(out_point) {
+        let header = provider.cell_header(out_point);
+        Some((output, data, header))
+    } else {
+        let genesis = Genesis::read();
+        genesis.cell(network, out_point).map(|(output, data)| {
+            let header = genesis.lookup(network).header();
+            (output, data, Some(header))
+        })
+    }
+}
+
+/// Finds a header in the provider at first, then checks the genesis header of the network.
+#[cfg(any(feature = "verifier", feature = "test-support"))]
+pub(crate) fn find_header<P: CellProvider>(
+    network: Network,
+    provider: &P,
+    block_hash: &packed::Byte32,
+) -> Option<HeaderView> {
+    provider.header(block_hash).or_else(|| {
+        let header = Genesis::read().lookup(network).header();
+        if &header.hash() == block_hash {
+            Some(header)
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(any(feature = "verifier", feature = "test-support"))]
+fn resolve_cell<P: CellProvider>(
+    network: Network,
+    provider: &P,
+    out_point: &packed::OutPoint,
+) -> Result<CellMeta> {
+    let (output, data, header) = find_cell(network, provider, out_point)
+        .ok_or_else(|| Error::Unresolvable(format!("cell {}", out_point)))?;
+    let builder = CellMetaBuilder::from_cell_output(output, data).out_point(out_point.clone());
+    let cell_meta = if let Some(header) = header {
+        // The index of the transaction in the block is never loaded by scripts.
+        let info = TransactionInfo::new(header.number(), header.epoch(), header.hash(), 0);
+        builder.transaction_info(info).build()
+    } else {
+        builder.build()
+    };
+    Ok(cell_meta)
+}
+
+/// Resolves the cell deps and the inputs of a transaction, the dep groups are expanded.
+#[cfg(any(feature = "verifier", feature = "test-support"))]
+pub(crate) fn resolve_transaction<P: CellProvider>(
+    network: Network,
+    provider: &P,
+    tx: &packed::Transaction,
+) -> Result<ResolvedTransaction> {
+    let transaction = tx.clone().into_view();
+    let mut resolved_cell_deps = Vec::new();
+    let mut resolved_dep_groups = Vec::new();
+    for cell_dep in transaction.cell_deps_iter() {
+        let cell_meta = resolve_cell(network, provider, &cell_dep.out_point())?;
+        let dep_type = DepType::try_from(cell_dep.dep_type())
+            .map_err(|err| Error::Unresolvable(err.to_string()))?;
+        match dep_type {
+            DepType::Code => resolved_cell_deps.push(cell_meta),
+            DepType::DepGroup => {
+                let (data, _) = cell_meta.mem_cell_data.clone().unwrap_or_default();
+                let members = packed::OutPointVec::from_slice(&data).map_err(|err| {
+                    Error::Unresolvable(format!("dep group {}: {}", cell_dep.out_point(), err))
+                })?;
+                for member in members.into_iter() {
+                    resolved_cell_deps.push(resolve_cell(network, provider, &member)?);
+                }
+                resolved_dep_groups.push(cell_meta);
+            }
+        }
+    }
+    let resolved_inputs = transaction
+        .input_pts_iter()
+        .map(|out_point| resolve_cell(network, provider, &out_point))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(ResolvedTransaction {
+        transaction,
+        resolved_cell_deps,
+        resolved_inputs,
+        resolved_dep_groups,
+    })
+}
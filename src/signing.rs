@@ -0,0 +1,234 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+mod multisig;
+
+pub use self::multisig::{MultisigConfig, MultisigSignatures};
+
+use ckb_crypto::secp::Privkey;
+use ckb_hash::{blake2b_256, new_blake2b};
+
+use ckb_types::{bytes::Bytes, packed, prelude::*, H256};
+
+use crate::error::{Error, Result};
+
+/// The size of a recoverable secp256k1 signature.
+pub const SIGNATURE_SIZE: usize = 65;
+
+/// Calculates the first 20 bytes of the blake2b hash.
+pub fn blake160(data: &[u8]) -> [u8; 20] {
+    let mut hash = [0u8; 20];
+    hash.copy_from_slice(&blake2b_256(data)[..20]);
+    hash
+}
+
+fn check_group(tx: &packed::Transaction, group: &[usize]) -> Result<()> {
+    let inputs_len = tx.raw().inputs().len();
+    if group.is_empty() {
+        Err(Error::InvalidScriptGroup("the group is empty".to_owned()))
+    } else if let Some(index) = group.iter().find(|index| **index >= inputs_len) {
+        Err(Error::InvalidScriptGroup(format!(
+            "input {} is out of bound, only {} inputs",
+            index, inputs_len
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+fn load_witness_args(tx: &packed::Transaction, index: usize) -> Result<packed::WitnessArgs> {
+    match tx.witnesses().get(index) {
+        Some(witness) if !witness.is_empty() => {
+            packed::WitnessArgs::from_slice(&witness.raw_data())
+                .map_err(|err| Error::InvalidWitness(index, err.to_string()))
+        }
+        _ => Ok(packed::WitnessArgs::default()),
+    }
+}
+
+/// Calculates the signing message for a script group, the lock in the witness of the first input
+/// in the group is replaced by a placeholder, which is zeros in the provided length.
+pub(crate) fn signing_message(
+    tx: &packed::Transaction,
+    group: &[usize],
+    placeholder_len: usize,
+) -> Result<H256> {
+    check_group(tx, group)?;
+    let witnesses = tx.witnesses();
+    let first_witness = load_witness_args(tx, group[0])?
+        .as_builder()
+        .lock(Some(Bytes::from(vec![0u8; placeholder_len])).pack())
+        .build()
+        .as_bytes();
+    let mut blake2b = new_blake2b();
+    blake2b.update(tx.calc_tx_hash().as_slice());
+    blake2b.update(&(first_witness.len() as u64).to_le_bytes());
+    blake2b.update(&first_witness);
+    let others = group[1..]
+        .iter()
+        .cloned()
+        .chain(tx.raw().inputs().len()..witnesses.len());
+    for index in others {
+        if let Some(witness) = witnesses.get(index) {
+            let witness = witness.raw_data();
+            blake2b.update(&(witness.len() as u64).to_le_bytes());
+            blake2b.update(&witness);
+        }
+    }
+    let mut message = [0u8; 32];
+    blake2b.finalize(&mut message);
+    Ok(message.into())
+}
+
+/// Calculates the signing message of `secp256k1_blake160_sighash_all` for a script group, which
+/// is the indexes of the inputs which have the same lock script.
+pub fn sighash_all_message(tx: &packed::Transaction, group: &[usize]) -> Result<H256> {
+    signing_message(tx, group, SIGNATURE_SIZE)
+}
+
+/// Sets the lock of the witness at the index, other fields in the witness are kept.
+///
+/// Empty witnesses are appended when the index is out of bound.
+pub fn set_witness_lock(
+    tx: &packed::Transaction,
+    index: usize,
+    lock: Bytes,
+) -> Result<packed::Transaction> {
+    let witness = load_witness_args(tx, index)?
+        .as_builder()
+        .lock(Some(lock).pack())
+        .build()
+        .as_bytes()
+        .pack();
+    let mut witnesses = tx.witnesses().into_iter().collect::<Vec<_>>();
+    if witnesses.len() <= index {
+        witnesses.resize(index + 1, packed::Bytes::default());
+    }
+    witnesses[index] = witness;
+    Ok(tx.clone().as_builder().witnesses(witnesses.pack()).build())
+}
+
+/// Puts the signature of `secp256k1_blake160_sighash_all` into the witness of the first input in
+/// the script group, as the same layout as the witness in `BaseStruct`.
+pub fn set_sighash_all_signature(
+    tx: &packed::Transaction,
+    group: &[usize],
+    signature: &[u8],
+) -> Result<packed::Transaction> {
+    check_group(tx, group)?;
+    if signature.len() != SIGNATURE_SIZE {
+        return Err(Error::Signing(format!(
+            "the size of signature should be {} but got {}",
+            SIGNATURE_SIZE,
+            signature.len()
+        )));
+    }
+    set_witness_lock(tx, group[0], Bytes::from(signature.to_vec()))
+}
+
+pub(crate) fn sign_message(privkey: &Privkey, message: &H256) -> Result<Vec<u8>> {
+    privkey
+        .sign_recoverable(message)
+        .map(|signature| signature.serialize())
+        .map_err(|err| Error::Signing(err.to_string()))
+}
+
+/// Signs a script group of `secp256k1_blake160_sighash_all` and puts the signature into the
+/// witness.
+pub fn sign_sighash_all(
+    tx: &packed::Transaction,
+    group: &[usize],
+    privkey: &Privkey,
+) -> Result<packed::Transaction> {
+    let message = sighash_all_message(tx, group)?;
+    let signature = sign_message(privkey, &message)?;
+    set_sighash_all_signature(tx, group, &signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use ckb_types::{bytes::Bytes, core::TransactionBuilder, h256, packed, prelude::*};
+
+    use super::{set_sighash_all_signature, sighash_all_message, SIGNATURE_SIZE};
+
+    fn witness_args(input_type: Option<&[u8]>, output_type: Option<&[u8]>) -> packed::Bytes {
+        packed::WitnessArgs::new_builder()
+            .input_type(input_type.map(|data| Bytes::from(data.to_vec())).pack())
+            .output_type(output_type.map(|data| Bytes::from(data.to_vec())).pack())
+            .build()
+            .as_bytes()
+            .pack()
+    }
+
+    // Two inputs in a group, the witness of the second input is empty and an extra witness is
+    // appended after the witnesses of inputs.
+    fn fixed_tx(extra_witness: &[u8]) -> packed::Transaction {
+        TransactionBuilder::default()
+            .input(packed::CellInput::new(packed::OutPoint::default(), 0))
+            .input(packed::CellInput::new(
+                packed::OutPoint::new(packed::Byte32::default(), 1),
+                0,
+            ))
+            .witness(witness_args(Some(&[0xab]), None))
+            .witness(Bytes::new().pack())
+            .witness(Bytes::from(extra_witness.to_vec()).pack())
+            .build()
+            .data()
+    }
+
+    #[test]
+    fn sighash_all_message_vector() {
+        let tx = fixed_tx(&[0x12, 0x34]);
+        assert_eq!(
+            tx.calc_tx_hash(),
+            h256!("0xa0628de259ebbd0056978ed3b7729d44c339d68e3360c8e15c292521cc356cbf").pack()
+        );
+        assert_eq!(
+            sighash_all_message(&tx, &[0, 1]).unwrap(),
+            h256!("0x58d9f485c8108a37c5ba56596eb0e6c52b312f37b346da128cba17a702881be0")
+        );
+    }
+
+    #[test]
+    fn sighash_all_message_with_extra_witnesses() {
+        let message = sighash_all_message(&fixed_tx(&[0x12, 0x34]), &[0, 1]).unwrap();
+        let changed = sighash_all_message(&fixed_tx(&[0x12, 0x35]), &[0, 1]).unwrap();
+        assert_ne!(message, changed);
+        let tx = fixed_tx(&[0x12, 0x34]);
+        let witnesses = tx.witnesses().into_iter().take(2).collect::<Vec<_>>();
+        let truncated = tx.as_builder().witnesses(witnesses.pack()).build();
+        let truncated = sighash_all_message(&truncated, &[0, 1]).unwrap();
+        assert_ne!(message, truncated);
+    }
+
+    #[test]
+    fn set_signature_keeps_other_fields() {
+        let tx = TransactionBuilder::default()
+            .input(packed::CellInput::new(packed::OutPoint::default(), 0))
+            .witness(witness_args(Some(&[0x01]), Some(&[0x02, 0x03])))
+            .build()
+            .data();
+        let signature = [0x55u8; SIGNATURE_SIZE];
+        let signed = set_sighash_all_signature(&tx, &[0], &signature).unwrap();
+        let witness = signed.witnesses().get(0).unwrap().raw_data();
+        let witness = packed::WitnessArgs::from_slice(&witness).unwrap();
+        assert_eq!(
+            witness.lock().to_opt().unwrap().raw_data(),
+            Bytes::from(signature.to_vec())
+        );
+        assert_eq!(
+            witness.input_type().to_opt().unwrap().raw_data(),
+            Bytes::from(vec![0x01])
+        );
+        assert_eq!(
+            witness.output_type().to_opt().unwrap().raw_data(),
+            Bytes::from(vec![0x02, 0x03])
+        );
+        assert!(set_sighash_all_signature(&tx, &[0], &signature[1..]).is_err());
+    }
+}
@@ -0,0 +1,323 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::{BTreeMap, HashSet};
+
+use ckb_crypto::secp::{Privkey, Pubkey, Signature};
+use property::Property;
+
+use ckb_types::{bytes::Bytes, core::ScriptHashType, packed, prelude::*, H256};
+
+use super::{blake160, set_witness_lock, sign_message, signing_message, SIGNATURE_SIZE};
+use crate::{
+    blockchain::{Bundled, Network},
+    error::{Error, Result},
+    since::Since,
+    system_deps::SystemDeps,
+};
+
+/// The config of `secp256k1_blake160_multisig_all`.
+///
+/// Serialized as `S | R | M | N | PubKeyHash1 | PubKeyHash2 | ...`, `S` is reserved and always
+/// be zero, `R` is the count of the first pubkeys which are required to sign, `M` is the
+/// threshold and `N` is the count of the pubkeys.
+#[derive(Property, Debug, Clone, PartialEq, Eq)]
+#[property(get(public), set(disable), mut(disable))]
+pub struct MultisigConfig {
+    require_first_n: u8,
+    threshold: u8,
+    pubkey_hashes: Vec<[u8; 20]>,
+}
+
+/// Collects signatures from several signers for a script group of
+/// `secp256k1_blake160_multisig_all`.
+#[derive(Property, Debug, Clone)]
+#[property(get(public), set(disable), mut(disable))]
+pub struct MultisigSignatures {
+    config: MultisigConfig,
+    group: Vec<usize>,
+    message: H256,
+    #[property(get(disable))]
+    signatures: BTreeMap<usize, Vec<u8>>,
+}
+
+impl MultisigConfig {
+    pub fn new(pubkey_hashes: Vec<[u8; 20]>, require_first_n: u8, threshold: u8) -> Result<Self> {
+        let invalid = |reason: &str| Error::Signing(format!("invalid multisig config: {}", reason));
+        if pubkey_hashes.is_empty() || pubkey_hashes.len() > usize::from(u8::max_value()) {
+            return Err(invalid("the count of pubkeys should be in [1, 255]"));
+        }
+        if threshold == 0 || usize::from(threshold) > pubkey_hashes.len() {
+            return Err(invalid("the threshold should be in [1, count of pubkeys]"));
+        }
+        if require_first_n > threshold {
+            return Err(invalid(
+                "the require first n should not be greater than the threshold",
+            ));
+        }
+        let unique = pubkey_hashes.iter().collect::<HashSet<_>>();
+        if unique.len() != pubkey_hashes.len() {
+            return Err(invalid("the pubkeys should be unique"));
+        }
+        Ok(Self {
+            require_first_n,
+            threshold,
+            pubkey_hashes,
+        })
+    }
+
+    pub fn from_pubkeys(pubkeys: &[Pubkey], require_first_n: u8, threshold: u8) -> Result<Self> {
+        let pubkey_hashes = pubkeys
+            .iter()
+            .map(|pubkey| blake160(&pubkey.serialize()))
+            .collect();
+        Self::new(pubkey_hashes, require_first_n, threshold)
+    }
+
+    pub fn serialize(&self) -> Bytes {
+        let mut data = Vec::with_capacity(4 + 20 * self.pubkey_hashes.len());
+        data.push(0);
+        data.push(self.require_first_n);
+        data.push(self.threshold);
+        data.push(self.pubkey_hashes.len() as u8);
+        for pubkey_hash in &self.pubkey_hashes {
+            data.extend_from_slice(&pubkey_hash[..]);
+        }
+        Bytes::from(data)
+    }
+
+    /// The blake160 of the serialized config.
+    pub fn hash160(&self) -> [u8; 20] {
+        blake160(&self.serialize())
+    }
+
+    /// The lock args, which is the blake160 of the serialized config, and an optional since.
+    pub fn lock_args(&self, since: Option<Since>) -> Result<Bytes> {
+        let mut args = self.hash160().to_vec();
+        if let Some(since) = since {
+            args.extend_from_slice(&since.encode()?.to_le_bytes());
+        }
+        Ok(Bytes::from(args))
+    }
+
+    pub fn lock_script(&self, network: Network, since: Option<Since>) -> Result<packed::Script> {
+        let cell =
+            SystemDeps::read().lookup_system_cell(network, Bundled::Secp256k1Blake160MultiSig)?;
+        let type_hash = cell.type_hash().clone().ok_or_else(|| {
+            Error::Unreachable(format!("multisig in {} has no type hash", network))
+        })?;
+        let script = packed::Script::new_builder()
+            .code_hash(type_hash.pack())
+            .hash_type(ScriptHashType::Type.into())
+            .args(self.lock_args(since)?.pack())
+            .build();
+        Ok(script)
+    }
+
+    /// The size of the lock in the witness: the serialized config and `M` signatures.
+    pub fn witness_lock_size(&self) -> usize {
+        self.serialize().len() + SIGNATURE_SIZE * usize::from(self.threshold)
+    }
+
+    /// Calculates the signing message for a script group, the lock in the witness of the first
+    /// input is replaced by a placeholder in the size of the final witness lock.
+    pub fn signing_message(&self, tx: &packed::Transaction, group: &[usize]) -> Result<H256> {
+        signing_message(tx, group, self.witness_lock_size())
+    }
+}
+
+impl MultisigSignatures {
+    pub fn new(config: MultisigConfig, tx: &packed::Transaction, group: &[usize]) -> Result<Self> {
+        let message = config.signing_message(tx, group)?;
+        Ok(Self {
+            config,
+            group: group.to_vec(),
+            message,
+            signatures: BTreeMap::new(),
+        })
+    }
+
+    fn pubkey_index(&self, pubkey: &Pubkey) -> Result<usize> {
+        let pubkey_hash = blake160(&pubkey.serialize());
+        self.config
+            .pubkey_hashes
+            .iter()
+            .position(|h| h == &pubkey_hash)
+            .ok_or_else(|| Error::Signing("the signer is not in the multisig config".to_owned()))
+    }
+
+    /// Signs the message by a signer in the multisig config.
+    pub fn sign(&mut self, privkey: &Privkey) -> Result<()> {
+        let pubkey = privkey
+            .pubkey()
+            .map_err(|err| Error::Signing(err.to_string()))?;
+        let index = self.pubkey_index(&pubkey)?;
+        let signature = sign_message(privkey, &self.message)?;
+        self.signatures.insert(index, signature);
+        Ok(())
+    }
+
+    /// Adds a partial signature which was signed by other signers.
+    ///
+    /// The signer is recovered from the signature, and should be in the multisig config.
+    pub fn add_signature(&mut self, signature: &[u8]) -> Result<()> {
+        let recovered = Signature::from_slice(signature)
+            .and_then(|sig| sig.recover(&self.message))
+            .map_err(|err| Error::Signing(err.to_string()))?;
+        let index = self.pubkey_index(&recovered)?;
+        self.signatures.insert(index, signature.to_vec());
+        Ok(())
+    }
+
+    pub fn signed_count(&self) -> usize {
+        self.signatures.len()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        let required = (0..usize::from(self.config.require_first_n))
+            .all(|index| self.signatures.contains_key(&index));
+        required && self.signatures.len() >= usize::from(self.config.threshold)
+    }
+
+    /// Assembles the lock in the witness: the serialized config and `M` signatures.
+    ///
+    /// Signatures of the first `R` pubkeys are always included.
+    pub fn witness_lock(&self) -> Result<Bytes> {
+        if !self.is_complete() {
+            return Err(Error::Signing(format!(
+                "not enough signatures, got {} but require {} with the first {}",
+                self.signatures.len(),
+                self.config.threshold,
+                self.config.require_first_n
+            )));
+        }
+        let mut lock = self.config.serialize().to_vec();
+        for signature in self
+            .signatures
+            .values()
+            .take(usize::from(self.config.threshold))
+        {
+            lock.extend_from_slice(signature);
+        }
+        Ok(Bytes::from(lock))
+    }
+
+    /// Puts the assembled lock into the witness of the first input in the script group.
+    pub fn apply(&self, tx: &packed::Transaction) -> Result<packed::Transaction> {
+        set_witness_lock(tx, self.group[0], self.witness_lock()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ckb_crypto::secp::Privkey;
+    use ckb_types::{core::TransactionBuilder, h160, h256, packed, prelude::*};
+
+    use super::{MultisigConfig, MultisigSignatures};
+    use crate::{signing::sign_message, since::Since};
+
+    fn fixed_privkeys() -> Vec<Privkey> {
+        vec![
+            h256!("0xd00c06bfd800d27397002dca6fb0993d5ba6399b4238b2f29ee9deb97593d2bc"),
+            h256!("0x63d86723e08f0f813a36ce6aa123bb2289d90680ae1e99d4de8cdb334553f24d"),
+            h256!("0x1111111111111111111111111111111111111111111111111111111111111111"),
+        ]
+        .into_iter()
+        .map(Privkey::from)
+        .collect()
+    }
+
+    fn fixed_config(require_first_n: u8) -> MultisigConfig {
+        let pubkeys = fixed_privkeys()
+            .iter()
+            .map(|privkey| privkey.pubkey().unwrap())
+            .collect::<Vec<_>>();
+        MultisigConfig::from_pubkeys(&pubkeys, require_first_n, 2).unwrap()
+    }
+
+    #[test]
+    fn lock_args_round_trip() {
+        let config = fixed_config(1);
+        let serialized = config.serialize();
+        let pubkey_hashes = [
+            h160!("0xc8328aabcd9b9e8e64fbc566c4385c3bdeb219d7"),
+            h160!("0x470dcdc5e44064909650113a274b3b36aecb6dc7"),
+            h160!("0xf949a9cc83edefcd580eb3f0f3bae187c4d008db"),
+        ];
+        let mut expected = vec![0, 1, 2, 3];
+        for pubkey_hash in &pubkey_hashes {
+            expected.extend_from_slice(pubkey_hash.as_bytes());
+        }
+        assert_eq!(serialized.as_ref(), &expected[..]);
+        let hash = h160!("0x3f606b98b08cfcc82fa880c97dfd84b372070f4b");
+        assert_eq!(config.lock_args(None).unwrap().as_ref(), hash.as_bytes());
+        let since = Since::AbsoluteBlockNumber(1024);
+        let args = config.lock_args(Some(since)).unwrap();
+        assert_eq!(&args[..20], hash.as_bytes());
+        assert_eq!(&args[20..], &[0, 4, 0, 0, 0, 0, 0, 0]);
+    }
+
+    fn unsigned_tx() -> packed::Transaction {
+        TransactionBuilder::default()
+            .input(packed::CellInput::new(packed::OutPoint::default(), 0))
+            .build()
+            .data()
+    }
+
+    #[test]
+    fn assemble_partial_signatures() {
+        let privkeys = fixed_privkeys();
+        let config = fixed_config(0);
+        let tx = unsigned_tx();
+        let mut signatures = MultisigSignatures::new(config.clone(), &tx, &[0]).unwrap();
+        for privkey in &privkeys[1..] {
+            let signature = sign_message(privkey, signatures.message()).unwrap();
+            signatures.add_signature(&signature).unwrap();
+        }
+        assert!(signatures.is_complete());
+        let lock = signatures.witness_lock().unwrap();
+        assert_eq!(lock.len(), config.witness_lock_size());
+        assert_eq!(
+            &lock[..config.serialize().len()],
+            config.serialize().as_ref()
+        );
+        let signed = signatures.apply(&tx).unwrap();
+        let witness = signed.witnesses().get(0).unwrap().raw_data();
+        let witness = packed::WitnessArgs::from_slice(&witness).unwrap();
+        assert_eq!(witness.lock().to_opt().unwrap().raw_data(), lock);
+    }
+
+    #[test]
+    fn require_the_first_signer() {
+        let privkeys = fixed_privkeys();
+        let tx = unsigned_tx();
+        let mut signatures = MultisigSignatures::new(fixed_config(1), &tx, &[0]).unwrap();
+        for privkey in &privkeys[1..] {
+            let signature = sign_message(privkey, signatures.message()).unwrap();
+            signatures.add_signature(&signature).unwrap();
+        }
+        assert_eq!(signatures.signed_count(), 2);
+        assert!(!signatures.is_complete());
+        assert!(signatures.witness_lock().is_err());
+        signatures.sign(&privkeys[0]).unwrap();
+        assert!(signatures.is_complete());
+        assert!(signatures.witness_lock().is_ok());
+    }
+
+    #[test]
+    fn reject_unknown_signer() {
+        let tx = unsigned_tx();
+        let mut signatures = MultisigSignatures::new(fixed_config(0), &tx, &[0]).unwrap();
+        let stranger = Privkey::from(h256!(
+            "0x2222222222222222222222222222222222222222222222222222222222222222"
+        ));
+        let signature = sign_message(&stranger, signatures.message()).unwrap();
+        assert!(signatures.add_signature(&signature).is_err());
+        assert_eq!(signatures.signed_count(), 0);
+    }
+}
@@ -0,0 +1,174 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::{convert::TryFrom, fmt};
+
+use ckb_types::core::EpochNumberWithFraction;
+
+use crate::error::{Error, Result};
+
+const RELATIVE_FLAG: u64 = 0x8000_0000_0000_0000;
+const METRIC_MASK: u64 = 0x6000_0000_0000_0000;
+const METRIC_BLOCK_NUMBER: u64 = 0x0000_0000_0000_0000;
+const METRIC_EPOCH: u64 = 0x2000_0000_0000_0000;
+const METRIC_TIMESTAMP: u64 = 0x4000_0000_0000_0000;
+const REMAIN_FLAGS_MASK: u64 = 0x1f00_0000_0000_0000;
+const VALUE_MASK: u64 = 0x00ff_ffff_ffff_ffff;
+
+/// The `since` field of a cell input.
+///
+/// Timestamps are in seconds, and compared with the median time of the previous blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Since {
+    AbsoluteBlockNumber(u64),
+    AbsoluteEpoch(EpochNumberWithFraction),
+    AbsoluteTimestamp(u64),
+    RelativeBlockNumber(u64),
+    RelativeEpoch(EpochNumberWithFraction),
+    RelativeTimestamp(u64),
+}
+
+fn check_epoch(value: u64, epoch: EpochNumberWithFraction) -> Result<()> {
+    if epoch.index() > 0 && epoch.index() >= epoch.length() {
+        Err(Error::InvalidSince(
+            value,
+            "the epoch fraction is greater than one",
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+impl Since {
+    pub fn is_relative(&self) -> bool {
+        matches!(
+            self,
+            Self::RelativeBlockNumber(_) | Self::RelativeEpoch(_) | Self::RelativeTimestamp(_)
+        )
+    }
+
+    pub fn is_absolute(&self) -> bool {
+        !self.is_relative()
+    }
+
+    /// Encodes into the raw `since` value, the value should be fit in 56 bits.
+    pub fn encode(&self) -> Result<u64> {
+        let (relative, metric, value) = match *self {
+            Self::AbsoluteBlockNumber(n) => (0, METRIC_BLOCK_NUMBER, n),
+            Self::AbsoluteEpoch(e) => (0, METRIC_EPOCH, e.full_value()),
+            Self::AbsoluteTimestamp(t) => (0, METRIC_TIMESTAMP, t),
+            Self::RelativeBlockNumber(n) => (RELATIVE_FLAG, METRIC_BLOCK_NUMBER, n),
+            Self::RelativeEpoch(e) => (RELATIVE_FLAG, METRIC_EPOCH, e.full_value()),
+            Self::RelativeTimestamp(t) => (RELATIVE_FLAG, METRIC_TIMESTAMP, t),
+        };
+        if value & !VALUE_MASK != 0 {
+            return Err(Error::InvalidSince(value, "the value is overflow"));
+        }
+        if let Self::AbsoluteEpoch(e) | Self::RelativeEpoch(e) = *self {
+            check_epoch(value, e)?;
+        }
+        Ok(relative | metric | value)
+    }
+
+    /// Decodes from a raw `since` value, unknown metric or any reserved flag bit is invalid.
+    pub fn decode(since: u64) -> Result<Self> {
+        if since & REMAIN_FLAGS_MASK != 0 {
+            return Err(Error::InvalidSince(
+                since,
+                "the reserved flags are not zero",
+            ));
+        }
+        let relative = since & RELATIVE_FLAG != 0;
+        let value = since & VALUE_MASK;
+        let decoded = match (relative, since & METRIC_MASK) {
+            (false, METRIC_BLOCK_NUMBER) => Self::AbsoluteBlockNumber(value),
+            (false, METRIC_EPOCH) => {
+                let epoch = EpochNumberWithFraction::from_full_value(value);
+                check_epoch(since, epoch)?;
+                Self::AbsoluteEpoch(epoch)
+            }
+            (false, METRIC_TIMESTAMP) => Self::AbsoluteTimestamp(value),
+            (true, METRIC_BLOCK_NUMBER) => Self::RelativeBlockNumber(value),
+            (true, METRIC_EPOCH) => {
+                let epoch = EpochNumberWithFraction::from_full_value(value);
+                check_epoch(since, epoch)?;
+                Self::RelativeEpoch(epoch)
+            }
+            (true, METRIC_TIMESTAMP) => Self::RelativeTimestamp(value),
+            _ => return Err(Error::InvalidSince(since, "the metric is unknown")),
+        };
+        Ok(decoded)
+    }
+}
+
+impl TryFrom<u64> for Since {
+    type Error = Error;
+    fn try_from(since: u64) -> Result<Self> {
+        Self::decode(since)
+    }
+}
+
+impl TryFrom<Since> for u64 {
+    type Error = Error;
+    fn try_from(since: Since) -> Result<Self> {
+        since.encode()
+    }
+}
+
+impl fmt::Display for Since {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::AbsoluteBlockNumber(n) => write!(f, "absolute block number {}", n),
+            Self::AbsoluteEpoch(e) => write!(f, "absolute epoch {}", e),
+            Self::AbsoluteTimestamp(t) => write!(f, "absolute timestamp {}", t),
+            Self::RelativeBlockNumber(n) => write!(f, "relative block number {}", n),
+            Self::RelativeEpoch(e) => write!(f, "relative epoch {}", e),
+            Self::RelativeTimestamp(t) => write!(f, "relative timestamp {}", t),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ckb_types::core::EpochNumberWithFraction;
+
+    use super::Since;
+    use crate::error::Error;
+
+    #[test]
+    fn bit_layout() {
+        let epoch = EpochNumberWithFraction::new(1, 2, 4);
+        let cases = [
+            (Since::AbsoluteBlockNumber(0x1234), 0x0000_0000_0000_1234),
+            (Since::AbsoluteEpoch(epoch), 0x2000_0400_0200_0001),
+            (Since::AbsoluteTimestamp(0x5fb0_0000), 0x4000_0000_5fb0_0000),
+            (Since::RelativeBlockNumber(100), 0x8000_0000_0000_0064),
+            (Since::RelativeEpoch(epoch), 0xa000_0400_0200_0001),
+            (Since::RelativeTimestamp(3600), 0xc000_0000_0000_0e10),
+        ];
+        for (since, raw) in cases.iter() {
+            assert_eq!(since.encode().unwrap(), *raw, "{}", since);
+            assert_eq!(Since::decode(*raw).unwrap(), *since);
+            assert_eq!(since.is_relative(), raw & 0x8000_0000_0000_0000 != 0);
+        }
+    }
+
+    #[test]
+    fn reject_invalid_values() {
+        let invalid = |result| matches!(result, Err(Error::InvalidSince(_, _)));
+        assert!(invalid(Since::AbsoluteBlockNumber(1 << 56).encode()));
+        // The fraction of the epoch is greater than one.
+        let epoch = EpochNumberWithFraction::from_full_value(0x0400_0500_0001);
+        assert!(invalid(Since::RelativeEpoch(epoch).encode()));
+        assert!(invalid(Since::decode(0x2000_0400_0500_0001).map(|_| 0)));
+        // A reserved flag is set.
+        assert!(invalid(Since::decode(0x0100_0000_0000_0000).map(|_| 0)));
+        // The metric is unknown.
+        assert!(invalid(Since::decode(0x6000_0000_0000_0000).map(|_| 0)));
+    }
+}
@@ -9,45 +9,31 @@
 use std::{collections::HashMap, convert::TryFrom};
 
 use property::Property;
-use serde_derive::Deserialize;
+use serde_derive::{Deserialize, Serialize};
 
-use ckb_types::H256;
+use ckb_types::{core::DepType, packed, prelude::*, H256};
 
 use crate::{
     blockchain::{Bundled, DepGroupId, Network},
     error::{Error, Result},
+    raw::{
+        RawDepGroup, RawMemberCell, RawSpecHashes, RawSystemCell, RawSystemDeps, FORMAT_VERSION,
+    },
 };
 
 lazy_static::lazy_static! {
     static ref SYSTEM_DEPS: SystemDeps = SystemDeps::initialize().unwrap();
 }
 
-#[derive(Deserialize)]
-struct RawSpecHashes {
-    genesis: H256,
-    cellbase: H256,
-    system_cells: Vec<RawSystemCell>,
-    dep_groups: Vec<RawDepGroup>,
-}
-
-#[derive(Deserialize)]
-struct RawSystemCell {
-    path: String,
-    tx_hash: H256,
-    index: usize,
-    data_hash: H256,
-    type_hash: Option<H256>,
-}
+pub struct SystemDeps(HashMap<Network, SpecHashes>);
 
-#[derive(Deserialize)]
-struct RawDepGroup {
-    included_cells: Vec<String>,
-    tx_hash: H256,
-    index: usize,
+/// The serialized spec hashes of a single chain, such as a chain which is not bundled.
+#[derive(Serialize, Deserialize)]
+struct RawSingleSpec {
+    version: u32,
+    spec: RawSpecHashes,
 }
 
-pub struct SystemDeps(HashMap<Network, SpecHashes>);
-
 #[derive(Property, Debug, Clone)]
 #[property(get(public), set(disable), mut(disable))]
 pub struct SpecHashes {
@@ -71,85 +57,345 @@ pub struct SystemCell {
 pub struct DepGroup {
     tx_hash: H256,
     index: usize,
+    #[property(get(disable))]
+    members: Vec<(Bundled, H256, usize)>,
 }
 
-fn network_from_spec_name(spec_name: &str) -> Result<Network> {
-    match spec_name {
-        "ckb" => Ok("mainnet"),
-        "ckb_testnet" => Ok("testnet"),
-        "ckb_staging" => Ok("staging"),
-        "ckb_dev" => Ok("develop"),
-        s => Ok(s),
+impl SystemCell {
+    pub fn out_point(&self) -> packed::OutPoint {
+        packed::OutPoint::new_builder()
+            .tx_hash(self.tx_hash.pack())
+            .index((self.index as u32).pack())
+            .build()
+    }
+
+    pub fn cell_dep(&self) -> packed::CellDep {
+        packed::CellDep::new_builder()
+            .out_point(self.out_point())
+            .dep_type(DepType::Code.into())
+            .build()
     }
-    .and_then(Network::try_from)
 }
 
-impl SystemDeps {
-    pub fn read() -> &'static Self {
-        &SYSTEM_DEPS
+impl DepGroup {
+    pub fn out_point(&self) -> packed::OutPoint {
+        packed::OutPoint::new_builder()
+            .tx_hash(self.tx_hash.pack())
+            .index((self.index as u32).pack())
+            .build()
     }
 
-    fn initialize() -> Result<Self> {
-        let raw_spec_hashes: HashMap<String, RawSpecHashes> =
-            toml::from_str(include_str!(concat!(env!("OUT_DIR"), "/hashes.toml"))).unwrap();
-        raw_spec_hashes
+    pub fn cell_dep(&self) -> packed::CellDep {
+        packed::CellDep::new_builder()
+            .out_point(self.out_point())
+            .dep_type(DepType::DepGroup.into())
+            .build()
+    }
+
+    /// The system cells which are included in the dep group, in the same order as `members()`.
+    pub fn included_cells(&self) -> Vec<Bundled> {
+        self.members
+            .iter()
+            .map(|(bundled, _, _)| *bundled)
+            .collect()
+    }
+
+    /// The out points which are listed in the data of the dep group cell.
+    pub fn members(&self) -> Vec<packed::OutPoint> {
+        self.member_cells()
+            .into_iter()
+            .map(|(_, out_point)| out_point)
+            .collect()
+    }
+
+    pub fn member_cells(&self) -> Vec<(Bundled, packed::OutPoint)> {
+        self.members
+            .iter()
+            .map(|(bundled, tx_hash, index)| {
+                let out_point = packed::OutPoint::new_builder()
+                    .tx_hash(tx_hash.pack())
+                    .index((*index as u32).pack())
+                    .build();
+                (*bundled, out_point)
+            })
+            .collect()
+    }
+}
+
+impl SpecHashes {
+    /// Loads the spec hashes of a single chain, for the chains which are not bundled.
+    ///
+    /// The format is `version = 1` and a `[spec]` table, which is the same as a table in
+    /// `SystemDeps::from_toml`, the system cells and dep groups should be the bundled ones.
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let raw: RawSingleSpec = toml::from_str(content)?;
+        Self::try_from(raw)
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        toml::to_string(&RawSingleSpec::from(self))
+            .map_err(|err| Error::Unreachable(format!("failed to serialize spec hashes: {}", err)))
+    }
+
+    /// Loads the spec hashes of a single chain in JSON.
+    pub fn from_json(content: &str) -> Result<Self> {
+        let raw: RawSingleSpec = serde_json::from_str(content)?;
+        Self::try_from(raw)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(&RawSingleSpec::from(self)).map_err(Into::into)
+    }
+
+    /// Iterates over the system cells, ordered by their indexes in the genesis cellbase.
+    pub fn iter_system_cells(&self) -> impl Iterator<Item = (Bundled, &SystemCell)> {
+        let mut system_cells = self
+            .system_cells
+            .iter()
+            .map(|(bundled, cell)| (*bundled, cell))
+            .collect::<Vec<_>>();
+        system_cells.sort_by_key(|(_, cell)| cell.index);
+        system_cells.into_iter()
+    }
+
+    /// Iterates over the dep groups, ordered by their indexes in the genesis transaction.
+    pub fn iter_dep_groups(&self) -> impl Iterator<Item = (DepGroupId, &DepGroup)> {
+        let mut dep_groups = self
+            .dep_groups
+            .iter()
+            .map(|(dep_group_id, dep_group)| (*dep_group_id, dep_group))
+            .collect::<Vec<_>>();
+        dep_groups.sort_by_key(|(_, dep_group)| dep_group.index);
+        dep_groups.into_iter()
+    }
+}
+
+impl From<RawSystemCell> for SystemCell {
+    fn from(raw: RawSystemCell) -> Self {
+        let RawSystemCell {
+            tx_hash,
+            index,
+            data_hash,
+            type_hash,
+            ..
+        } = raw;
+        Self {
+            tx_hash,
+            index,
+            data_hash,
+            type_hash,
+        }
+    }
+}
+
+impl DepGroup {
+    fn from_raw(raw: RawDepGroup) -> Result<(DepGroupId, Self)> {
+        let RawDepGroup {
+            included_cells,
+            tx_hash,
+            index,
+            members,
+        } = raw;
+        if included_cells.len() != members.len() {
+            return Err(Error::InvalidSpecHashes(format!(
+                "dep group {:#x}#{} has {} included cells but {} members",
+                tx_hash,
+                index,
+                included_cells.len(),
+                members.len()
+            )));
+        }
+        let included_cells = included_cells
+            .into_iter()
+            .map(|path| Bundled::try_from(path.as_str()))
+            .collect::<Result<Vec<Bundled>>>()?;
+        let dep_group_id = DepGroupId::try_from(&included_cells[..])?;
+        let members = included_cells
+            .into_iter()
+            .zip(members.into_iter())
+            .map(|(bundled, member)| (bundled, member.tx_hash, member.index))
+            .collect();
+        let dep_group = Self {
+            tx_hash,
+            index,
+            members,
+        };
+        Ok((dep_group_id, dep_group))
+    }
+}
+
+impl TryFrom<RawSpecHashes> for SpecHashes {
+    type Error = Error;
+    fn try_from(raw: RawSpecHashes) -> Result<Self> {
+        let RawSpecHashes {
+            genesis,
+            cellbase,
+            system_cells,
+            dep_groups,
+        } = raw;
+        let system_cells = system_cells
             .into_iter()
-            .map(|(spec_name, raw_spec_values)| {
-                let network = network_from_spec_name(&spec_name)?;
-                let RawSpecHashes {
-                    genesis,
-                    cellbase,
-                    system_cells,
-                    dep_groups,
-                } = raw_spec_values;
-                let system_cells = system_cells
-                    .into_iter()
-                    .map(|raw_system_cell| {
-                        let RawSystemCell {
-                            path,
-                            tx_hash,
-                            index,
-                            data_hash,
-                            type_hash,
-                        } = raw_system_cell;
-                        let bundled = Bundled::try_from(path.as_str())?;
-                        let system_cell = SystemCell {
-                            tx_hash,
-                            index,
-                            data_hash,
-                            type_hash,
+            .map(|raw_system_cell| {
+                let bundled = Bundled::try_from(raw_system_cell.path.as_str())?;
+                Ok((bundled, raw_system_cell.into()))
+            })
+            .collect::<Result<HashMap<_, _>>>()?;
+        let dep_groups = dep_groups
+            .into_iter()
+            .map(DepGroup::from_raw)
+            .collect::<Result<HashMap<_, _>>>()?;
+        Ok(Self {
+            genesis,
+            cellbase,
+            system_cells,
+            dep_groups,
+        })
+    }
+}
+
+impl From<&SpecHashes> for RawSpecHashes {
+    fn from(spec: &SpecHashes) -> Self {
+        let system_cells = spec
+            .iter_system_cells()
+            .map(|(bundled, cell)| RawSystemCell {
+                path: bundled.resource_path().to_owned(),
+                tx_hash: cell.tx_hash.clone(),
+                index: cell.index,
+                data_hash: cell.data_hash.clone(),
+                type_hash: cell.type_hash.clone(),
+            })
+            .collect();
+        let dep_groups = spec
+            .iter_dep_groups()
+            .map(|(_, dep_group)| {
+                let (included_cells, members) = dep_group
+                    .members
+                    .iter()
+                    .map(|(bundled, tx_hash, index)| {
+                        let member = RawMemberCell {
+                            tx_hash: tx_hash.clone(),
+                            index: *index,
                         };
-                        Ok((bundled, system_cell))
+                        (bundled.resource_path().to_owned(), member)
                     })
-                    .collect::<Result<HashMap<_, _>>>()?;
-                let dep_groups = dep_groups
-                    .into_iter()
-                    .map(|raw_dep_group| {
-                        let RawDepGroup {
-                            included_cells,
-                            tx_hash,
-                            index,
-                        } = raw_dep_group;
-                        let included_cells = included_cells
-                            .into_iter()
-                            .map(|path| Bundled::try_from(path.as_str()))
-                            .collect::<Result<Vec<Bundled>>>()?;
-                        let dep_group_id = DepGroupId::try_from(&included_cells[..])?;
-                        let dep_group = DepGroup { tx_hash, index };
-                        Ok((dep_group_id, dep_group))
-                    })
-                    .collect::<Result<HashMap<_, _>>>()?;
-                let spec_hashes = SpecHashes {
-                    genesis,
-                    cellbase,
-                    system_cells,
-                    dep_groups,
-                };
-                Ok((network, spec_hashes))
+                    .unzip();
+                RawDepGroup {
+                    included_cells,
+                    tx_hash: dep_group.tx_hash.clone(),
+                    index: dep_group.index,
+                    members,
+                }
+            })
+            .collect();
+        Self {
+            genesis: spec.genesis.clone(),
+            cellbase: spec.cellbase.clone(),
+            system_cells,
+            dep_groups,
+        }
+    }
+}
+
+impl TryFrom<RawSingleSpec> for SpecHashes {
+    type Error = Error;
+    fn try_from(raw: RawSingleSpec) -> Result<Self> {
+        if raw.version != FORMAT_VERSION {
+            return Err(Error::UnsupportedVersion(raw.version));
+        }
+        Self::try_from(raw.spec)
+    }
+}
+
+impl From<&SpecHashes> for RawSingleSpec {
+    fn from(spec: &SpecHashes) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            spec: spec.into(),
+        }
+    }
+}
+
+impl TryFrom<RawSystemDeps> for SystemDeps {
+    type Error = Error;
+    fn try_from(raw: RawSystemDeps) -> Result<Self> {
+        if raw.version != FORMAT_VERSION {
+            return Err(Error::UnsupportedVersion(raw.version));
+        }
+        raw.specs
+            .into_iter()
+            .map(|(spec_name, raw)| {
+                let network = Network::from_spec_name(&spec_name)?;
+                Ok((network, SpecHashes::try_from(raw)?))
             })
             .collect::<Result<HashMap<_, _>>>()
             .map(Self)
     }
+}
+
+impl From<&SystemDeps> for RawSystemDeps {
+    fn from(system_deps: &SystemDeps) -> Self {
+        let specs = system_deps
+            .0
+            .iter()
+            .map(|(network, spec)| (network.spec_name().to_owned(), spec.into()))
+            .collect();
+        Self {
+            version: FORMAT_VERSION,
+            specs,
+        }
+    }
+}
+
+impl SystemDeps {
+    pub fn read() -> &'static Self {
+        &SYSTEM_DEPS
+    }
+
+    fn initialize() -> Result<Self> {
+        Self::from_toml(include_str!(concat!(env!("OUT_DIR"), "/hashes.toml")))
+    }
+
+    /// Loads spec hashes from the versioned format, which is the same as the bundled one.
+    ///
+    /// The spec names should be the names of the known networks, such as `ckb` or `ckb_dev`,
+    /// and all system cells and dep groups should be known. The spec hashes of other chains
+    /// could be loaded by `SpecHashes::from_toml`.
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let raw: RawSystemDeps = toml::from_str(content)?;
+        Self::try_from(raw)
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        toml::to_string(&RawSystemDeps::from(self))
+            .map_err(|err| Error::Unreachable(format!("failed to serialize spec hashes: {}", err)))
+    }
+
+    /// Loads spec hashes from the versioned format in JSON.
+    pub fn from_json(content: &str) -> Result<Self> {
+        let raw: RawSystemDeps = serde_json::from_str(content)?;
+        Self::try_from(raw)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(&RawSystemDeps::from(self)).map_err(Into::into)
+    }
+
+    pub(crate) fn specs(&self) -> &HashMap<Network, SpecHashes> {
+        &self.0
+    }
+
+    pub fn lookup_spec(&self, network: Network) -> Result<&SpecHashes> {
+        self.0
+            .get(&network)
+            .ok_or_else(|| Error::Unknown(Network::NAME, network.to_string()))
+    }
+
+    /// Iterates over the spec hashes of all networks, ordered by the networks.
+    pub fn iter_specs(&self) -> impl Iterator<Item = (Network, &SpecHashes)> {
+        Network::all()
+            .iter()
+            .filter_map(move |network| self.0.get(network).map(|spec| (*network, spec)))
+    }
 
     pub fn lookup_system_cell(&self, network: Network, key: Bundled) -> Result<&SystemCell> {
         self.0
@@ -165,3 +411,81 @@ impl SystemDeps {
             .ok_or(Error::UnknownDepGroup(network, key))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::{SpecHashes, SystemDeps};
+    use crate::{
+        blockchain::{Bundled, DepGroupId, Network},
+        error::Error,
+        raw::RawSystemDeps,
+    };
+
+    #[test]
+    fn bundled_order() {
+        for (network, spec) in SystemDeps::read().iter_specs() {
+            let system_cells = spec
+                .iter_system_cells()
+                .map(|(bundled, _)| bundled)
+                .collect::<Vec<_>>();
+            assert_eq!(&system_cells[..], Bundled::all(), "{}", network);
+            let dep_groups = spec
+                .iter_dep_groups()
+                .map(|(dep_group_id, _)| dep_group_id)
+                .collect::<Vec<_>>();
+            assert_eq!(&dep_groups[..], DepGroupId::all(), "{}", network);
+        }
+    }
+
+    #[test]
+    fn bundled_round_trip() {
+        let bundled = SystemDeps::read();
+        let toml = bundled.to_toml().unwrap();
+        assert_eq!(
+            SystemDeps::from_toml(&toml).unwrap().to_toml().unwrap(),
+            toml
+        );
+        let json = bundled.to_json().unwrap();
+        assert_eq!(
+            SystemDeps::from_json(&json).unwrap().to_toml().unwrap(),
+            toml
+        );
+    }
+
+    #[test]
+    fn single_spec_round_trip() {
+        let spec = SystemDeps::read().lookup_spec(Network::Develop).unwrap();
+        let toml = spec.to_toml().unwrap();
+        let loaded = SpecHashes::from_toml(&toml).unwrap();
+        assert_eq!(loaded.genesis(), spec.genesis());
+        assert_eq!(loaded.to_toml().unwrap(), toml);
+        let json = spec.to_json().unwrap();
+        assert_eq!(
+            SpecHashes::from_json(&json).unwrap().to_toml().unwrap(),
+            toml
+        );
+    }
+
+    #[test]
+    fn load_spec_of_custom_chain() {
+        // A custom chain shares the bundled scripts but has its own genesis.
+        let spec = SystemDeps::read().lookup_spec(Network::Develop).unwrap();
+        let genesis = format!("{:#x}", spec.genesis());
+        let custom = format!("0x{}", "ab".repeat(32));
+        let toml = spec.to_toml().unwrap().replace(&genesis, &custom);
+        let loaded = SpecHashes::from_toml(&toml).unwrap();
+        assert_eq!(format!("{:#x}", loaded.genesis()), custom);
+        assert!(SpecHashes::from_toml(&toml.replace("version = 1", "version = 2")).is_err());
+    }
+
+    #[test]
+    fn reject_truncated_dep_group_members() {
+        let mut raw = RawSystemDeps::from(SystemDeps::read());
+        let spec = raw.specs.values_mut().next().unwrap();
+        spec.dep_groups[0].members.pop();
+        let result = SystemDeps::try_from(raw);
+        assert!(matches!(result, Err(Error::InvalidSpecHashes(_))));
+    }
+}
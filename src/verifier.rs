@@ -0,0 +1,188 @@
+// Copyright (C) 2019-2020 Boyu Yang
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use ckb_script::TransactionScriptsVerifier;
+use ckb_script_data_loader::DataLoader;
+use ckb_types::{
+    bytes::Bytes,
+    core::{
+        cell::{CellMeta, ResolvedTransaction},
+        BlockExt, Cycle, EpochExt, HeaderView,
+    },
+    packed,
+};
+
+use crate::{
+    blockchain::Network,
+    consensus::ConsensusParams,
+    error::{Error, Result},
+    resolve::{find_cell, find_header, resolve_transaction, CellProvider},
+};
+
+/// Verifies the scripts of transactions without a node.
+///
+/// The cells are looked up in the provider at first, then in the genesis block of the network,
+/// so the system cells are always available unless the provider reports them as spent.
+pub struct OfflineVerifier<'a, P> {
+    network: Network,
+    provider: &'a P,
+}
+
+impl<'a, P: CellProvider> OfflineVerifier<'a, P> {
+    pub fn new(network: Network, provider: &'a P) -> Self {
+        Self { network, provider }
+    }
+
+    /// Resolves the cell deps and the inputs of a transaction, the dep groups are expanded.
+    pub fn resolve(&self, tx: &packed::Transaction) -> Result<ResolvedTransaction> {
+        resolve_transaction(self.network, self.provider, tx)
+    }
+
+    /// Runs all lock scripts and type scripts of a transaction, returns the consumed cycles.
+    pub fn verify(&self, tx: &packed::Transaction, max_cycles: Cycle) -> Result<Cycle> {
+        let resolved = self.resolve(tx)?;
+        TransactionScriptsVerifier::new(&resolved, self)
+            .verify(max_cycles)
+            .map_err(|err| Error::Verification(err.to_string()))
+    }
+
+    /// Runs the scripts of a transaction with the max block cycles of the network.
+    pub fn verify_with_default_cycles(&self, tx: &packed::Transaction) -> Result<Cycle> {
+        let max_cycles = ConsensusParams::read()
+            .lookup(self.network)
+            .max_block_cycles();
+        self.verify(tx, max_cycles)
+    }
+}
+
+impl<'a, P: CellProvider> DataLoader for OfflineVerifier<'a, P> {
+    fn load_cell_data(&self, cell: &CellMeta) -> Option<(Bytes, packed::Byte32)> {
+        cell.mem_cell_data.clone().or_else(|| {
+            find_cell(self.network, self.provider, &cell.out_point)
+                .map(|(_, data, _)| (data.clone(), packed::CellOutput::calc_data_hash(&data)))
+        })
+    }
+
+    fn get_block_ext(&self, _block_hash: &packed::Byte32) -> Option<BlockExt> {
+        None
+    }
+
+    fn get_header(&self, block_hash: &packed::Byte32) -> Option<HeaderView> {
+        find_header(self.network, self.provider, block_hash)
+    }
+
+    fn get_block_epoch(&self, _block_hash: &packed::Byte32) -> Option<EpochExt> {
+        None
+    }
+}
+
+#[cfg(all(test, feature = "test-support"))]
+mod tests {
+    use ckb_types::{
+        bytes::Bytes,
+        core::{Capacity, TransactionBuilder},
+        packed,
+        prelude::*,
+    };
+
+    use super::OfflineVerifier;
+    use crate::{
+        blockchain::{Bundled, Network},
+        dev_accounts::DevAccounts,
+        error::Error,
+        mock_chain::MockChain,
+        signing::{set_witness_lock, sign_sighash_all, MultisigConfig, MultisigSignatures},
+        system_deps::SystemDeps,
+    };
+
+    fn transfer(
+        input: packed::OutPoint,
+        lock: packed::Script,
+        capacity: Capacity,
+        scripts: &[Bundled],
+    ) -> packed::Transaction {
+        let cell_deps = SystemDeps::read()
+            .plan_cell_deps(Network::Develop, scripts)
+            .unwrap();
+        let output = packed::CellOutput::new_builder()
+            .capacity(capacity.pack())
+            .lock(lock)
+            .build();
+        TransactionBuilder::default()
+            .cell_deps(cell_deps)
+            .input(packed::CellInput::new(input, 0))
+            .output(output)
+            .output_data(Bytes::new().pack())
+            .build()
+            .data()
+    }
+
+    fn flip_signature(tx: &packed::Transaction) -> packed::Transaction {
+        let witness = tx.witnesses().get(0).unwrap().raw_data();
+        let witness = packed::WitnessArgs::from_slice(&witness).unwrap();
+        let mut lock = witness.lock().to_opt().unwrap().raw_data().to_vec();
+        // The last byte is the recovery id, flip a byte of the signature itself.
+        let index = lock.len() - 2;
+        lock[index] ^= 0x01;
+        set_witness_lock(tx, 0, Bytes::from(lock)).unwrap()
+    }
+
+    #[test]
+    fn verify_sighash_all() {
+        let account = DevAccounts::read().get(0).unwrap();
+        let capacity = account
+            .capacity()
+            .safe_sub(Capacity::shannons(1000))
+            .unwrap();
+        let tx = transfer(
+            account.out_point().clone(),
+            account.lock_script().clone(),
+            capacity,
+            &[Bundled::Secp256k1Blake160],
+        );
+        let signed = sign_sighash_all(&tx, &[0], account.privkey()).unwrap();
+        let chain = MockChain::new(Network::Develop);
+        let verifier = OfflineVerifier::new(Network::Develop, &chain);
+        assert!(verifier.verify_with_default_cycles(&signed).is_ok());
+        let result = verifier.verify_with_default_cycles(&flip_signature(&signed));
+        assert!(matches!(result, Err(Error::Verification(_))));
+    }
+
+    #[test]
+    fn verify_multisig() {
+        let accounts = DevAccounts::read().accounts();
+        let pubkeys = accounts
+            .iter()
+            .map(|account| account.pubkey().clone())
+            .collect::<Vec<_>>();
+        let config = MultisigConfig::from_pubkeys(&pubkeys, 0, 2).unwrap();
+        let lock = config.lock_script(Network::Develop, None).unwrap();
+        let capacity = Capacity::shannons(100_000_000_000);
+        let output = packed::CellOutput::new_builder()
+            .capacity(capacity.pack())
+            .lock(lock.clone())
+            .build();
+        let mut chain = MockChain::new(Network::Develop);
+        let out_point = chain.add_cell(output, Bytes::new());
+        let tx = transfer(
+            out_point,
+            lock,
+            capacity,
+            &[Bundled::Secp256k1Blake160MultiSig],
+        );
+        let mut signatures = MultisigSignatures::new(config, &tx, &[0]).unwrap();
+        for account in accounts {
+            signatures.sign(account.privkey()).unwrap();
+        }
+        let signed = signatures.apply(&tx).unwrap();
+        let verifier = OfflineVerifier::new(Network::Develop, &chain);
+        assert!(verifier.verify_with_default_cycles(&signed).is_ok());
+        let result = verifier.verify_with_default_cycles(&flip_signature(&signed));
+        assert!(matches!(result, Err(Error::Verification(_))));
+    }
+}